@@ -1,13 +1,21 @@
 use std::time::{self, Duration, SystemTime, UNIX_EPOCH};
 
+use crate::{
+    error::{errorf, Error},
+    value::format,
+};
+
+/// the most elements a vector or matrix may have
+const MAX_ELEMS: usize = 1 << 24;
+
 #[allow(unused)]
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Config {
     prompt: String,
     format: String,
     rat_format: String,
-    format_verb: String,
-    format_prec: usize,
+    format_verb: Option<char>,
+    format_prec: Option<usize>,
     format_float: bool,
     origin: usize,
     seed: u64,
@@ -22,25 +30,27 @@ pub struct Config {
     output_base: usize,
 }
 
-impl Config {
-    pub fn input_base(&self) -> usize {
-        self.input_base
-    }
-
-    pub fn prompt(&self) -> &str {
-        self.prompt.as_ref()
-    }
+/// Options are the settings a [`Config`] starts with, as given by the command
+/// line. The defaults are those of [`Config::default`].
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub prompt: String,
+    pub format: String,
+    pub origin: usize,
+    pub seed: u64,
+    pub max_bits: usize,
+    pub max_digits: usize,
+    pub max_stack: usize,
+    pub float_prec: usize,
+    pub input_base: usize,
+    pub output_base: usize,
 }
 
-impl Default for Config {
+impl Default for Options {
     fn default() -> Self {
         Self {
             prompt: String::from("> "),
             format: String::new(),
-            rat_format: String::new(),
-            format_verb: String::new(),
-            format_prec: 0,
-            format_float: false,
             origin: 1,
             seed: SystemTime::now()
                 .duration_since(UNIX_EPOCH)
@@ -50,11 +60,188 @@ impl Default for Config {
             max_digits: 10_000,
             max_stack: 100_000,
             float_prec: 256,
+            input_base: 0,
+            output_base: 0,
+        }
+    }
+}
+
+impl Config {
+    /// returns a configuration with the settings in `opts`
+    pub fn new(opts: Options) -> Self {
+        let mut conf = Self {
+            prompt: opts.prompt,
+            format: String::new(),
+            rat_format: String::new(),
+            format_verb: None,
+            format_prec: None,
+            format_float: false,
+            origin: opts.origin,
+            seed: opts.seed,
+            max_bits: opts.max_bits,
+            max_digits: opts.max_digits,
+            max_stack: opts.max_stack,
+            float_prec: opts.float_prec,
             real_time: Duration::default(),
             user_time: Duration::default(),
             sys_time: Duration::default(),
-            input_base: 0,
-            output_base: 0,
+            input_base: opts.input_base,
+            output_base: opts.output_base,
+        };
+        conf.set_format(opts.format);
+        conf
+    }
+
+    /// the base in which numbers are read, or 0 for decimal with `0x` and `0o`
+    /// prefixes
+    pub fn input_base(&self) -> usize {
+        self.input_base
+    }
+
+    pub fn set_input_base(&mut self, base: usize) {
+        self.input_base = base;
+    }
+
+    /// the base in which numbers are printed, or 0 for decimal
+    pub fn output_base(&self) -> usize {
+        self.output_base
+    }
+
+    pub fn set_output_base(&mut self, base: usize) {
+        self.output_base = base;
+    }
+
+    pub fn prompt(&self) -> &str {
+        self.prompt.as_ref()
+    }
+
+    pub fn set_prompt(&mut self, prompt: String) {
+        self.prompt = prompt;
+    }
+
+    /// the printf-style format for printing numbers, or empty for the default
+    pub fn format(&self) -> &str {
+        self.format.as_ref()
+    }
+
+    /// sets the format, along with the settings derived from it. A format
+    /// that [`format::verb`] rejects is kept, but derives nothing.
+    pub fn set_format(&mut self, format: String) {
+        let verb = format::verb(&format).ok();
+        self.format_verb = verb.map(|(v, _)| v);
+        self.format_prec = verb.and_then(|(_, p)| p);
+        self.format_float =
+            matches!(self.format_verb, Some(v) if "eEfFgG".contains(v));
+        self.rat_format = if self.format_float {
+            String::new()
+        } else {
+            format.clone()
+        };
+        self.format = format;
+    }
+
+    /// the format for the numerator and denominator of a rational, which
+    /// print as `n/d`. It is the format unless that prints rationals as
+    /// decimals.
+    pub fn rat_format(&self) -> &str {
+        self.rat_format.as_ref()
+    }
+
+    /// the verb of the format, such as `g` for `%.20g`
+    pub fn format_verb(&self) -> Option<char> {
+        self.format_verb
+    }
+
+    /// the precision given in the format, such as 20 for `%.20g`
+    pub fn format_prec(&self) -> Option<usize> {
+        self.format_prec
+    }
+
+    /// whether the format is a floating-point one, in which case rationals
+    /// print as decimals
+    pub fn format_float(&self) -> bool {
+        self.format_float
+    }
+
+    /// the number of bits in the mantissa of floating-point values
+    pub fn float_prec(&self) -> usize {
+        self.float_prec
+    }
+
+    pub fn set_float_prec(&mut self, prec: usize) {
+        self.float_prec = prec;
+    }
+
+    /// the index of the first element of a vector, and the first value
+    /// produced by iota
+    pub fn origin(&self) -> usize {
+        self.origin
+    }
+
+    pub fn set_origin(&mut self, origin: usize) {
+        self.origin = origin;
+    }
+
+    /// the seed for the random numbers generated by `?`
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    /// the maximum number of bits in an integer result, or in either part of a
+    /// rational one, or 0 for no limit. Arithmetic, shifts, factorials and
+    /// number literals that would exceed it are errors.
+    pub fn max_bits(&self) -> usize {
+        self.max_bits
+    }
+
+    /// returns an error if a result of `bits` bits would be larger than
+    /// [`Config::max_bits`] allows
+    pub fn check_bits(&self, bits: u64) -> Result<(), Error> {
+        if self.max_bits > 0 && bits > self.max_bits as u64 {
+            errorf!("result exceeds {} bits", self.max_bits);
+        }
+        Ok(())
+    }
+
+    /// returns an error if a vector or matrix of `len` elements would be
+    /// larger than allowed
+    pub fn check_len(&self, len: usize) -> Result<(), Error> {
+        if len > MAX_ELEMS {
+            errorf!("result exceeds {MAX_ELEMS} elements");
         }
+        Ok(())
+    }
+
+    pub fn set_max_bits(&mut self, bits: usize) {
+        self.max_bits = bits;
+    }
+
+    /// the maximum number of digits in a printed integer before it is shown in
+    /// scientific notation instead, or 0 for no limit
+    pub fn max_digits(&self) -> usize {
+        self.max_digits
+    }
+
+    pub fn set_max_digits(&mut self, digits: usize) {
+        self.max_digits = digits;
+    }
+
+    /// the maximum depth of calls to user-defined ops
+    pub fn max_stack(&self) -> usize {
+        self.max_stack
+    }
+
+    pub fn set_max_stack(&mut self, depth: usize) {
+        self.max_stack = depth;
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new(Options::default())
     }
 }
@@ -0,0 +1,559 @@
+//! a line editor for the interactive REPL. It reads keys from a terminal in
+//! raw mode and offers the Emacs-style keys of readline, a history that is
+//! kept in `~/.algae_history`, and completion of names with tab. An
+//! [`Editor`] is a [`Read`], so the scanner reads the lines typed into it
+//! just as it reads a file.
+
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    io::{self, Read, Stdin, Stdout, Write},
+    ops::Range,
+    os::fd::{AsRawFd, RawFd},
+    path::PathBuf,
+    sync::RwLock,
+};
+
+use crate::exec::context::Context;
+
+use history::History;
+
+mod complete;
+mod history;
+mod term;
+
+/// the name of the history file in the home directory
+const HISTORY_FILE: &str = ".algae_history";
+
+/// Editor reads lines from a terminal, letting them be edited as they are
+/// typed. It prompts with the configured prompt, or with as many spaces for
+/// the lines of a multi-line op body.
+pub struct Editor<'a, R: Read, W: Write> {
+    context: &'a RwLock<Context<'a>>,
+    input: R,
+    output: W,
+    /// the terminal to put in raw mode while a line is read, if any
+    fd: Option<RawFd>,
+    history: History,
+    line: Line,
+    /// the position in the history of the line being edited
+    index: usize,
+    /// the line as it was before moving through the history
+    edited: Vec<char>,
+    /// the text last killed, for yanking back
+    killed: Vec<char>,
+    /// the rest of the last line read, not yet handed out by `read`
+    pending: VecDeque<u8>,
+    /// whether the lines being read are the body of an op
+    continuing: bool,
+}
+
+/// a key, decoded from the bytes the terminal sends for it
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Key {
+    Char(char),
+    Ctrl(char),
+    Meta(char),
+    Enter,
+    Tab,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    Unknown,
+}
+
+/// Line is the text being edited and the position of the cursor in it
+#[derive(Debug, Default)]
+struct Line {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl<'a> Editor<'a, Stdin, Stdout> {
+    /// returns an editor for the terminal on standard input and output, with
+    /// its history in the home directory
+    pub fn stdio(context: &'a RwLock<Context<'a>>) -> Self {
+        let mut editor = Self::new(context, io::stdin(), io::stdout());
+        editor.fd = Some(io::stdin().as_raw_fd());
+        if let Some(home) = std::env::var_os("HOME") {
+            let file = PathBuf::from(home).join(HISTORY_FILE);
+            editor.history = History::load(file);
+            editor.index = editor.history.len();
+        }
+        editor
+    }
+}
+
+impl<'a, R: Read, W: Write> Editor<'a, R, W> {
+    /// returns an editor that reads keys from `input` and echoes to `output`,
+    /// which are not set up as a terminal, and has no history file
+    pub fn new(context: &'a RwLock<Context<'a>>, input: R, output: W) -> Self {
+        Self {
+            context,
+            input,
+            output,
+            fd: None,
+            history: History::default(),
+            line: Line::default(),
+            index: 0,
+            edited: Vec::new(),
+            killed: Vec::new(),
+            pending: VecDeque::new(),
+            continuing: false,
+        }
+    }
+
+    /// reads a line after printing `prompt`, returning it without its
+    /// newline, or `None` at the end of the input
+    pub fn read_line(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        let _raw = self.fd.map(term::RawMode::enable).transpose()?;
+        self.line = Line::default();
+        self.index = self.history.len();
+        self.refresh(prompt)?;
+        loop {
+            let Some(key) = self.key()? else {
+                // the input ended partway through a line, which is the last
+                write!(self.output, "\r\n")?;
+                let text = self.line.text();
+                return Ok((!text.is_empty()).then_some(text));
+            };
+            match key {
+                Key::Enter => {
+                    write!(self.output, "\r\n")?;
+                    let text = self.line.text();
+                    self.history.add(&text);
+                    return Ok(Some(text));
+                }
+                Key::Ctrl('d') if self.line.chars.is_empty() => {
+                    write!(self.output, "\r\n")?;
+                    return Ok(None);
+                }
+                Key::Ctrl('c') => {
+                    write!(self.output, "^C\r\n")?;
+                    self.line = Line::default();
+                    self.index = self.history.len();
+                }
+                Key::Tab => self.complete()?,
+                Key::Up | Key::Ctrl('p') if self.index > 0 => {
+                    if self.index == self.history.len() {
+                        self.edited = self.line.chars.clone();
+                    }
+                    self.index -= 1;
+                    let prev = self.history.get(self.index).unwrap_or_default();
+                    self.line.set(prev.chars().collect());
+                }
+                Key::Down | Key::Ctrl('n')
+                    if self.index < self.history.len() =>
+                {
+                    self.index += 1;
+                    let next = match self.history.get(self.index) {
+                        Some(next) => next.chars().collect(),
+                        None => self.edited.clone(),
+                    };
+                    self.line.set(next);
+                }
+                Key::Ctrl('l') => write!(self.output, "\x1b[H\x1b[2J")?,
+                key => self.edit(key),
+            }
+            self.refresh(prompt)?;
+        }
+    }
+
+    /// applies a key that changes the line or moves the cursor in it. Keys
+    /// that mean nothing here are ignored.
+    fn edit(&mut self, key: Key) {
+        let line = &mut self.line;
+        let end = line.chars.len();
+        match key {
+            Key::Char(c) => line.insert(&[c]),
+            Key::Home | Key::Ctrl('a') => line.pos = 0,
+            Key::End | Key::Ctrl('e') => line.pos = end,
+            Key::Left | Key::Ctrl('b') => line.pos = line.pos.saturating_sub(1),
+            Key::Right | Key::Ctrl('f') => line.pos = end.min(line.pos + 1),
+            Key::Meta('b') => line.pos = line.word_start(),
+            Key::Meta('f') => line.pos = line.word_end(),
+            Key::Backspace if line.pos > 0 => {
+                line.kill(line.pos - 1..line.pos);
+            }
+            Key::Delete | Key::Ctrl('d') if line.pos < end => {
+                line.kill(line.pos..line.pos + 1);
+            }
+            Key::Ctrl('k') => self.killed = line.kill(line.pos..end),
+            Key::Ctrl('u') => self.killed = line.kill(0..line.pos),
+            Key::Ctrl('w') | Key::Meta('\x7f') => {
+                self.killed = line.kill(line.word_start()..line.pos);
+            }
+            Key::Meta('d') => {
+                self.killed = line.kill(line.pos..line.word_end());
+            }
+            Key::Ctrl('y') => line.insert(&self.killed),
+            Key::Ctrl('t') => line.transpose(),
+            _ => {}
+        }
+    }
+
+    /// completes the word before the cursor as far as the names it might be,
+    /// followed by a space if there is just one. If it can go no further,
+    /// the names are listed below the line.
+    fn complete(&mut self) -> io::Result<()> {
+        let before: String = self.line.chars[..self.line.pos].iter().collect();
+        // the context is busy only if the line is being read in the middle
+        // of changing it, when there is nothing sensible to complete
+        let Ok(context) = self.context.try_read() else {
+            return Ok(());
+        };
+        let (start, names) = complete::complete(&context, &before);
+        drop(context);
+        // every name starts with the word, so their common prefix does too
+        let word = before.get(start..).unwrap_or_default();
+        match complete::common_prefix(&names).strip_prefix(word) {
+            Some(rest) if !rest.is_empty() => {
+                let rest: Vec<_> = rest.chars().collect();
+                self.line.insert(&rest);
+                if names.len() == 1 {
+                    self.line.insert(&[' ']);
+                }
+            }
+            _ if names.len() > 1 => {
+                write!(self.output, "\r\n{}\r\n", names.join("  "))?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// redraws the line after `prompt`, scrolled sideways if need be to keep
+    /// the cursor on the screen, and puts the cursor in place
+    fn refresh(&mut self, prompt: &str) -> io::Result<()> {
+        let cols = self.fd.map_or(80, term::width);
+        let plen = prompt.chars().count();
+        let (mut start, mut pos) = (0, self.line.pos);
+        while plen + pos >= cols && pos > 0 {
+            start += 1;
+            pos -= 1;
+        }
+        let len =
+            (self.line.chars.len() - start).min(cols.saturating_sub(plen));
+        let text: String = self.line.chars[start..start + len].iter().collect();
+        write!(self.output, "\r{prompt}{text}\x1b[0K\r")?;
+        if plen + pos > 0 {
+            write!(self.output, "\x1b[{}C", plen + pos)?;
+        }
+        self.output.flush()
+    }
+
+    /// reads a key, or returns `None` at the end of the input
+    fn key(&mut self) -> io::Result<Option<Key>> {
+        let Some(b) = self.byte()? else {
+            return Ok(None);
+        };
+        let key = match b {
+            b'\r' | b'\n' => Key::Enter,
+            b'\t' => Key::Tab,
+            0x08 | 0x7f => Key::Backspace,
+            0x1b => self.escape()?,
+            1..=26 => Key::Ctrl(char::from(b'a' + b - 1)),
+            0..=31 => Key::Unknown,
+            0x80.. => {
+                // the rest of a UTF-8 sequence, whose length is given by the
+                // leading ones of its first byte
+                let mut bytes = vec![b];
+                for _ in 1..b.leading_ones() {
+                    bytes.extend(self.byte()?);
+                }
+                match std::str::from_utf8(&bytes) {
+                    Ok(s) => Key::Char(s.chars().next().unwrap()),
+                    Err(_) => Key::Unknown,
+                }
+            }
+            b => Key::Char(char::from(b)),
+        };
+        Ok(Some(key))
+    }
+
+    /// decodes the key whose sequence starts with an escape: an arrow or
+    /// other special key, or a key pressed with meta
+    fn escape(&mut self) -> io::Result<Key> {
+        let Some(b) = self.byte()? else {
+            return Ok(Key::Unknown);
+        };
+        if b != b'[' && b != b'O' {
+            return Ok(Key::Meta(char::from(b).to_ascii_lowercase()));
+        }
+        // a control sequence: any parameters, then a final byte
+        let mut params = String::new();
+        let last = loop {
+            match self.byte()? {
+                Some(c @ 0x40..=0x7e) => break c,
+                Some(c) => params.push(char::from(c)),
+                None => return Ok(Key::Unknown),
+            }
+        };
+        Ok(match (last, params.as_str()) {
+            (b'A', _) => Key::Up,
+            (b'B', _) => Key::Down,
+            (b'C', _) => Key::Right,
+            (b'D', _) => Key::Left,
+            (b'H', _) | (b'~', "1" | "7") => Key::Home,
+            (b'F', _) | (b'~', "4" | "8") => Key::End,
+            (b'~', "3") => Key::Delete,
+            _ => Key::Unknown,
+        })
+    }
+
+    /// reads a byte, or returns `None` at the end of the input
+    fn byte(&mut self) -> io::Result<Option<u8>> {
+        let mut b = [0];
+        loop {
+            match self.input.read(&mut b) {
+                Ok(0) => return Ok(None),
+                Ok(_) => return Ok(Some(b[0])),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// returns the prompt for the next line
+    fn prompt(&self) -> String {
+        let prompt = match self.context.try_read() {
+            Ok(context) => context.config().prompt().to_owned(),
+            Err(_) => String::new(),
+        };
+        if self.continuing {
+            " ".repeat(prompt.chars().count())
+        } else {
+            prompt
+        }
+    }
+}
+
+impl<R: Read, W: Write> Read for Editor<'_, R, W> {
+    /// hands out the lines that are read, each with its newline, reading
+    /// another when the last has been used up
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pending.is_empty() {
+            let prompt = self.prompt();
+            let Some(line) = self.read_line(&prompt)? else {
+                return Ok(0);
+            };
+            self.continuing = if self.continuing {
+                !line.trim().is_empty()
+            } else {
+                opens_body(&line)
+            };
+            self.pending.extend(line.bytes().chain(*b"\n"));
+        }
+        let n = buf.len().min(self.pending.len());
+        for (b, p) in buf.iter_mut().zip(self.pending.drain(..n)) {
+            *b = p;
+        }
+        Ok(n)
+    }
+}
+
+impl<R: Read, W: Write> Debug for Editor<'_, R, W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Editor")
+            .field("fd", &self.fd)
+            .field("line", &self.line)
+            .field("continuing", &self.continuing)
+            .finish_non_exhaustive()
+    }
+}
+
+/// reports whether `line` starts the definition of an op whose body is on the
+/// lines that follow, up to a blank one
+fn opens_body(line: &str) -> bool {
+    let code = line.split('#').next().unwrap_or_default().trim();
+    code.strip_prefix("op")
+        .is_some_and(|rest| rest.starts_with(char::is_whitespace))
+        && code.ends_with('=')
+}
+
+/// reports whether `c` is part of a word, for the keys that move by words
+fn is_word(c: char) -> bool {
+    c == '_' || c.is_alphanumeric()
+}
+
+impl Line {
+    fn text(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    /// replaces the text, leaving the cursor at the end
+    fn set(&mut self, chars: Vec<char>) {
+        self.pos = chars.len();
+        self.chars = chars;
+    }
+
+    /// inserts `chars` at the cursor and moves the cursor past them
+    fn insert(&mut self, chars: &[char]) {
+        self.chars.splice(self.pos..self.pos, chars.iter().copied());
+        self.pos += chars.len();
+    }
+
+    /// removes the text in `range`, leaving the cursor where it was, and
+    /// returns it
+    fn kill(&mut self, range: Range<usize>) -> Vec<char> {
+        self.pos = range.start;
+        self.chars.drain(range).collect()
+    }
+
+    /// swaps the characters on either side of the cursor, or the last two at
+    /// the end of the line, and moves the cursor forward
+    fn transpose(&mut self) {
+        let len = self.chars.len();
+        if self.pos == 0 || len < 2 {
+            return;
+        }
+        let pos = self.pos.min(len - 1);
+        self.chars.swap(pos - 1, pos);
+        self.pos = pos + 1;
+    }
+
+    /// returns the start of the word before the cursor
+    fn word_start(&self) -> usize {
+        let mut pos = self.pos;
+        while pos > 0 && !is_word(self.chars[pos - 1]) {
+            pos -= 1;
+        }
+        while pos > 0 && is_word(self.chars[pos - 1]) {
+            pos -= 1;
+        }
+        pos
+    }
+
+    /// returns the end of the word after the cursor
+    fn word_end(&self) -> usize {
+        let mut pos = self.pos;
+        while pos < self.chars.len() && !is_word(self.chars[pos]) {
+            pos += 1;
+        }
+        while pos < self.chars.len() && is_word(self.chars[pos]) {
+            pos += 1;
+        }
+        pos
+    }
+}
+
+#[test]
+fn keys() {
+    use crate::config::Config;
+    let conf = Config::default();
+    let context = RwLock::new(Context::new(&conf));
+    let keys = concat!(
+        // home, right, delete, and insert
+        "2+3\x01\x1b[C\x1b[3~*\r",
+        // back through the history, and a character before the cursor
+        "\x1b[A\x02\x7f/\r",
+        // kill a word and yank it back at the start
+        "abc def\x17\x01\x19 \r",
+        // transpose, then complete and cancel
+        "ba\x14\r",
+        "sq\t4\r",
+        "oops\x03\x04",
+    );
+    let mut editor = Editor::new(&context, keys.as_bytes(), Vec::new());
+    let mut line = || editor.read_line("> ").unwrap();
+    assert_eq!(line().as_deref(), Some("2*3"));
+    assert_eq!(line().as_deref(), Some("2/3"));
+    assert_eq!(line().as_deref(), Some("def abc "));
+    assert_eq!(line().as_deref(), Some("ab"));
+    assert_eq!(line().as_deref(), Some("sqrt 4"));
+    assert_eq!(line(), None);
+}
+
+#[test]
+fn complete_non_ascii() {
+    use crate::{config::Config, parse::Parser, scan::Scanner};
+    let conf = Config::default();
+    let context = RwLock::new(Context::new(&conf));
+    let defs = "cafés = 1\ncafébabe = 2\n".as_bytes();
+    let scanner = Scanner::new(&context, "test", defs);
+    let mut parser = Parser::new("test", scanner, &context);
+    assert!(parser.run_all(&context, false, true));
+    // multibyte characters before the word, in it and in the completion
+    let keys = "π + caf\t\ré÷ sq\t2\r";
+    let mut editor = Editor::new(&context, keys.as_bytes(), Vec::new());
+    let mut line = || editor.read_line("> ").unwrap();
+    assert_eq!(line().as_deref(), Some("π + café"));
+    assert_eq!(line().as_deref(), Some("é÷ sqrt 2"));
+}
+
+#[test]
+fn continuation() {
+    use crate::config::Config;
+    let conf = Config::default();
+    let context = RwLock::new(Context::new(&conf));
+    let input = "op f x =\nx + 1\n\nf 2\n";
+    let mut editor = Editor::new(&context, input.as_bytes(), Vec::new());
+    let mut text = String::new();
+    editor.read_to_string(&mut text).unwrap();
+    assert_eq!(text, input);
+    let output = String::from_utf8(editor.output).unwrap();
+    // the body and the blank line after it get the continuation prompt, and
+    // the last prompt meets the end of the input
+    let prompts: Vec<_> = output
+        .split("\r\n")
+        .filter_map(|l| l.strip_prefix('\r'))
+        .filter_map(|l| l.split_once(|c| c != '>' && c != ' '))
+        .map(|(prompt, _)| prompt)
+        .collect();
+    assert_eq!(prompts, ["> ", "  ", "  ", "> ", "> "]);
+}
+
+#[test]
+fn pty() {
+    use crate::config::Config;
+    use std::{fs::File, os::fd::FromRawFd, ptr};
+    let (mut master, mut slave) = (0, 0);
+    // SAFETY: openpty fills in two new file descriptors
+    let res = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            ptr::null_mut(),
+            ptr::null(),
+            ptr::null(),
+        )
+    };
+    assert_eq!(res, 0, "openpty: {}", io::Error::last_os_error());
+    // SAFETY: the descriptors are new and owned by nothing else
+    let (mut keys, tty) =
+        unsafe { (File::from_raw_fd(master), File::from_raw_fd(slave)) };
+    let canonical = || {
+        let mut t = std::mem::MaybeUninit::uninit();
+        // SAFETY: tcgetattr fills in `t` when it succeeds
+        unsafe {
+            assert_eq!(libc::tcgetattr(slave, t.as_mut_ptr()), 0);
+            t.assume_init().c_lflag & libc::ICANON != 0
+        }
+    };
+    let conf = Config::default();
+    let context = RwLock::new(Context::new(&conf));
+    let mut editor = Editor::new(
+        &context,
+        tty.try_clone().unwrap(),
+        tty.try_clone().unwrap(),
+    );
+    editor.fd = Some(slave);
+    std::thread::scope(|s| {
+        s.spawn(|| {
+            // type only once the terminal is raw, as a person would
+            while canonical() {
+                std::thread::yield_now();
+            }
+            keys.write_all(b"iota\x1b[D\x1b[D\x1b[D\x1b[Dx = \r")
+                .unwrap();
+        });
+        let line = editor.read_line("> ").unwrap();
+        assert_eq!(line.as_deref(), Some("x = iota"));
+    });
+    assert!(canonical(), "terminal left in raw mode");
+}
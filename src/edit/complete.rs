@@ -0,0 +1,89 @@
+use crate::{
+    exec::context::Context,
+    parse::SPECIALS,
+    value::eval::{binary, unary},
+};
+
+/// returns the start of the word that ends at the end of `line`, where the
+/// cursor is, along with the names it might be completed to, sorted. A word
+/// right after a `)` at the start of the line is a special command; any other
+/// is a builtin, a user-defined op, a global variable or `op`. An empty word
+/// completes to nothing.
+pub(super) fn complete(context: &Context, line: &str) -> (usize, Vec<String>) {
+    let start = line
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| c == '_' || c.is_alphanumeric())
+        .last()
+        .map_or(line.len(), |(i, _)| i);
+    let word = &line[start..];
+    if word.is_empty() {
+        return (start, Vec::new());
+    }
+    let names: Vec<&str> = if line[..start].trim_start() == ")" {
+        SPECIALS.to_vec()
+    } else {
+        let ops = context.ops().map(|fun| fun.name.as_str());
+        let globals = context.globals().into_iter().map(|(name, _)| name);
+        unary::WORDS
+            .iter()
+            .chain(binary::WORDS)
+            .copied()
+            .chain(["op"])
+            .chain(ops)
+            .chain(globals)
+            .collect()
+    };
+    let mut names: Vec<_> = names
+        .into_iter()
+        .filter(|name| name.starts_with(word))
+        .map(String::from)
+        .collect();
+    names.sort();
+    names.dedup();
+    (start, names)
+}
+
+/// returns the longest prefix that all of `names` share
+pub(super) fn common_prefix(names: &[String]) -> &str {
+    let Some(first) = names.first() else {
+        return "";
+    };
+    let mut len = first.len();
+    for name in &names[1..] {
+        len = first
+            .char_indices()
+            .zip(name.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((i, a), _)| i + a.len_utf8())
+            .min(len);
+    }
+    &first[..len]
+}
+
+#[test]
+fn names() {
+    use crate::{config::Config, parse::eval_lines};
+    use std::sync::RwLock;
+    for word in unary::WORDS {
+        assert!(unary::is_unary_op(word), "{word}");
+    }
+    for word in binary::WORDS {
+        assert!(binary::is_binary_op(word), "{word}");
+    }
+    let conf = Config::default();
+    let context = RwLock::new(Context::new(&conf));
+    let input = "op double x = 2 * x\ndone = 1\n";
+    assert!(eval_lines(&context, input).iter().all(Result::is_ok));
+    let context = context.read().unwrap();
+    let (start, names) = complete(&context, "3 + do");
+    assert_eq!(start, 4);
+    assert_eq!(names, ["done", "double", "down"]);
+    assert_eq!(common_prefix(&names), "do");
+    assert_eq!(
+        complete(&context, ")ma").1,
+        ["maxbits", "maxdigits", "maxstack"]
+    );
+    assert_eq!(complete(&context, "1 ").1, Vec::<String>::new());
+}
@@ -0,0 +1,85 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+
+/// the most lines kept in the history
+const MAX_LINES: usize = 1000;
+
+/// History is the list of lines entered, oldest first. When it has a file,
+/// the lines are read from it at the start and added to it as they are
+/// entered, so they survive from one session to the next.
+#[derive(Debug, Default)]
+pub(super) struct History {
+    lines: Vec<String>,
+    file: Option<PathBuf>,
+}
+
+impl History {
+    /// returns the history kept in `file`, reading the lines it already
+    /// holds. A file that cannot be read starts an empty history.
+    pub(super) fn load(file: PathBuf) -> Self {
+        let mut lines: Vec<_> = fs::read_to_string(&file)
+            .unwrap_or_default()
+            .lines()
+            .map(String::from)
+            .collect();
+        if lines.len() > MAX_LINES {
+            lines.drain(..lines.len() - MAX_LINES);
+            // keep the file from growing without bound
+            let _ = fs::write(&file, lines.join("\n") + "\n");
+        }
+        Self {
+            lines,
+            file: Some(file),
+        }
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub(super) fn get(&self, i: usize) -> Option<&str> {
+        self.lines.get(i).map(String::as_str)
+    }
+
+    /// adds `line` to the end of the history, unless it is blank or repeats
+    /// the last line. Failing to write the file is not an error: the line is
+    /// still remembered for this session.
+    pub(super) fn add(&mut self, line: &str) {
+        if line.trim().is_empty()
+            || self.lines.last().is_some_and(|l| l == line)
+        {
+            return;
+        }
+        self.lines.push(String::from(line));
+        if self.lines.len() > MAX_LINES {
+            self.lines.remove(0);
+        }
+        if let Some(file) = &self.file {
+            let _ = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(file)
+                .and_then(|mut f| writeln!(f, "{line}"));
+        }
+    }
+}
+
+#[test]
+fn persistent() {
+    let file = std::env::temp_dir()
+        .join(format!("algae_history_test_{}", std::process::id()));
+    let _ = fs::remove_file(&file);
+    let mut history = History::load(file.clone());
+    history.add("1 + 2");
+    history.add("1 + 2");
+    history.add("  ");
+    history.add("iota 3");
+    let history = History::load(file.clone());
+    fs::remove_file(&file).unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history.get(0), Some("1 + 2"));
+    assert_eq!(history.get(1), Some("iota 3"));
+}
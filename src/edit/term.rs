@@ -0,0 +1,65 @@
+use std::{io, mem::MaybeUninit, os::fd::RawFd};
+
+/// RawMode puts a terminal into raw mode, in which keys are read one at a time
+/// as they are typed, without echo or signals. The old settings are restored
+/// when it is dropped.
+pub(super) struct RawMode {
+    fd: RawFd,
+    saved: libc::termios,
+}
+
+impl RawMode {
+    pub(super) fn enable(fd: RawFd) -> io::Result<Self> {
+        let mut saved = MaybeUninit::uninit();
+        // SAFETY: tcgetattr fills in `saved` when it succeeds
+        let saved = unsafe {
+            if libc::tcgetattr(fd, saved.as_mut_ptr()) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            saved.assume_init()
+        };
+        let mut raw = saved;
+        raw.c_iflag &= !(libc::BRKINT
+            | libc::ICRNL
+            | libc::INPCK
+            | libc::ISTRIP
+            | libc::IXON);
+        raw.c_oflag &= !libc::OPOST;
+        raw.c_cflag |= libc::CS8;
+        raw.c_lflag &= !(libc::ECHO | libc::ICANON | libc::IEXTEN | libc::ISIG);
+        raw.c_cc[libc::VMIN] = 1;
+        raw.c_cc[libc::VTIME] = 0;
+        // TCSADRAIN rather than TCSAFLUSH, so typed-ahead lines are kept
+        // SAFETY: `raw` is a valid termios
+        if unsafe { libc::tcsetattr(fd, libc::TCSADRAIN, &raw) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { fd, saved })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        // SAFETY: `saved` came from tcgetattr
+        unsafe {
+            libc::tcsetattr(self.fd, libc::TCSADRAIN, &self.saved);
+        }
+    }
+}
+
+/// returns the width of the terminal `fd` in columns, or 80 if it cannot be
+/// found
+pub(super) fn width(fd: RawFd) -> usize {
+    let mut size = MaybeUninit::<libc::winsize>::zeroed();
+    // SAFETY: TIOCGWINSZ fills in a winsize
+    let size = unsafe {
+        if libc::ioctl(fd, libc::TIOCGWINSZ, size.as_mut_ptr()) != 0 {
+            return 80;
+        }
+        size.assume_init()
+    };
+    match size.ws_col {
+        0 => 80,
+        cols => usize::from(cols),
+    }
+}
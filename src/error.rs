@@ -0,0 +1,92 @@
+use std::fmt::Display;
+
+/// Error is the reason a line of input could not be run. Both kinds carry the
+/// name of the source and the line the error occurred on, which are filled in
+/// by the parser with [`Error::at`] once the error reaches it; errors raised
+/// during evaluation start out without a location.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// the input could not be scanned or parsed
+    Parse {
+        msg: String,
+        file: String,
+        line: usize,
+    },
+
+    /// the input was well formed but evaluating it failed
+    Eval {
+        msg: String,
+        file: String,
+        line: usize,
+    },
+}
+
+impl Error {
+    /// returns a parse error with the message and no location
+    pub fn parse(msg: impl Into<String>) -> Self {
+        Self::Parse {
+            msg: msg.into(),
+            file: String::new(),
+            line: 0,
+        }
+    }
+
+    /// returns an evaluation error with the message and no location
+    pub fn eval(msg: impl Into<String>) -> Self {
+        Self::Eval {
+            msg: msg.into(),
+            file: String::new(),
+            line: 0,
+        }
+    }
+
+    /// sets the location of the error, unless it already has one
+    pub fn at(mut self, name: &str, lineno: usize) -> Self {
+        let (Self::Parse { file, line, .. } | Self::Eval { file, line, .. }) =
+            &mut self;
+        if file.is_empty() {
+            *file = name.to_owned();
+            *line = lineno;
+        }
+        self
+    }
+
+    pub fn msg(&self) -> &str {
+        let (Self::Parse { msg, .. } | Self::Eval { msg, .. }) = self;
+        msg
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// formats the error as `file:line: message`, or just the message if it has
+/// no location
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (Self::Parse { msg, file, line } | Self::Eval { msg, file, line }) =
+            self;
+        if file.is_empty() {
+            write!(f, "{msg}")
+        } else {
+            write!(f, "{file}:{line}: {msg}")
+        }
+    }
+}
+
+/// returns early from the enclosing function with an evaluation error built
+/// from the format arguments
+macro_rules! errorf {
+    ($($args:tt)*) => {
+        return Err($crate::error::Error::eval(format!($($args)*)))
+    };
+}
+
+pub(crate) use errorf;
+
+#[test]
+fn located() {
+    let err = Error::eval("division by zero").at("demo.ivy", 3);
+    assert_eq!(err.to_string(), "demo.ivy:3: division by zero");
+    // the first location wins
+    assert_eq!(err.at("<stdin>", 7).to_string(), "demo.ivy:3: division by zero");
+}
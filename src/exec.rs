@@ -53,46 +53,183 @@ pub mod operator {
 
 pub mod function {
 
-    use crate::value::{
-        context::{expr::Expr, BinaryOp, UnaryOp},
-        Value,
+    use std::sync::Arc;
+
+    use crate::{
+        error::{errorf, Error},
+        value::{
+            context::{expr::Expr, BinaryOp, UnaryOp},
+            Value,
+        },
     };
 
-    #[allow(unused)]
+    use super::context::Context;
+
+    /// Function is a user-defined unary or binary operator
     #[derive(Clone, Debug, Default)]
     pub struct Function {
         pub is_binary: bool,
         pub name: String,
         pub left: String,
         pub right: String,
-        body: Vec<Expr>,
+        pub(crate) body: Vec<Expr>,
+
+        /// the arguments and the variables assigned in the body, in the order
+        /// of their local indexes
         pub(crate) locals: Vec<String>,
-        globals: Vec<String>,
+
+        /// the global variables referenced in the body
+        pub(crate) globals: Vec<String>,
+    }
+
+    /// writes the op as the ivy source that defines it. A body of more than
+    /// one statement is written over several lines, ending with a blank one.
+    impl std::fmt::Display for Function {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "op ")?;
+            if self.is_binary {
+                write!(f, "{} ", self.left)?;
+            }
+            write!(f, "{} {} =", self.name, self.right)?;
+            if let [stmt] = self.body.as_slice() {
+                return write!(f, " {}", stmt.prog_string());
+            }
+            for stmt in &self.body {
+                write!(f, "\n\t{}", stmt.prog_string())?;
+            }
+            writeln!(f)
+        }
     }
 
     impl Function {
         pub fn name(&self) -> &str {
             self.name.as_ref()
         }
+
+        /// evaluates the body in a new stack frame whose first locals are
+        /// `args`, returning the value of the last expression or of the first
+        /// conditional whose condition is true. A call to a user-defined op
+        /// that produces the result is made in place of this one rather than
+        /// nested within it, so loops written as tail recursion run in
+        /// constant space. Each such call still counts toward
+        /// [`Config::max_stack`](crate::config::Config::max_stack), so
+        /// endless recursion stops with an error.
+        fn call(
+            self: &Arc<Self>,
+            ctx: &mut Context,
+            mut args: Vec<Value>,
+        ) -> Result<Value, Error> {
+            let mut fun = Arc::clone(self);
+            let mut tail_calls = 0;
+            loop {
+                ctx.push(&fun, tail_calls)?;
+                for (i, arg) in args.into_iter().enumerate() {
+                    ctx.assign_local(i + 1, arg);
+                }
+                let res = fun.eval_body(ctx);
+                ctx.pop();
+                match res? {
+                    Tail::Value(v) => return Ok(v),
+                    Tail::Call(next, next_args) => {
+                        fun = next;
+                        args = next_args;
+                        tail_calls += 1;
+                    }
+                }
+            }
+        }
+
+        fn eval_body(&self, ctx: &mut Context) -> Result<Tail, Error> {
+            let Some((last, body)) = self.body.split_last() else {
+                errorf!("no value returned by `{}`", self.name);
+            };
+            for e in body {
+                if let Expr::Conditional { binary } = e {
+                    let cond = binary.left.eval(ctx)?;
+                    if is_true(&self.name, cond)? {
+                        return eval_tail(&binary.right, ctx);
+                    }
+                    continue;
+                }
+                e.eval(ctx)?;
+            }
+            if let Expr::Conditional { binary } = last {
+                let cond = binary.left.eval(ctx)?;
+                if is_true(&self.name, cond)? {
+                    return eval_tail(&binary.right, ctx);
+                }
+                errorf!("no value returned by `{}`", self.name);
+            }
+            eval_tail(last, ctx)
+        }
+    }
+
+    /// the result of evaluating an expression in tail position: either a
+    /// value, or a call to a user-defined op that is still to be made
+    enum Tail {
+        Value(Value),
+        Call(Arc<Function>, Vec<Value>),
     }
 
-    impl<'a> UnaryOp<'a> for &'a Function {
+    /// evaluates `e`, except that if it is a call to a user-defined op only the
+    /// arguments are evaluated, leaving the call to the caller
+    fn eval_tail(e: &Expr, ctx: &mut Context) -> Result<Tail, Error> {
+        match e {
+            Expr::Unary { unary } => {
+                if let Some(fun) = ctx.unary_fn.get(&unary.op).cloned() {
+                    let right = unary.right.eval(ctx)?;
+                    return Ok(Tail::Call(fun, vec![right]));
+                }
+            }
+            Expr::Binary { binary } => {
+                if let Some(fun) = ctx.binary_fn.get(&binary.op).cloned() {
+                    let right = binary.right.eval(ctx)?;
+                    let left = binary.left.eval(ctx)?;
+                    return Ok(Tail::Call(fun, vec![left, right]));
+                }
+            }
+            _ => {}
+        }
+        Ok(Tail::Value(e.eval(ctx)?))
+    }
+
+    /// reports whether `v`, the condition of a conditional in the op `name`,
+    /// is true: a non-zero number or character, or a vector holding one
+    fn is_true(name: &str, v: Value) -> Result<bool, Error> {
+        match v {
+            Value::Char(c) => Ok(c != '\0'),
+            Value::Vector(v) if v.len() == 1 => {
+                is_true(name, v.into_iter().next().unwrap())
+            }
+            Value::Int(_)
+            | Value::BigInt(_)
+            | Value::Rational(_)
+            | Value::Float(_)
+            | Value::Complex(_) => Ok(!v.is_zero()),
+            v => errorf!(
+                "invalid expression {v} for conditional inside `{name}`"
+            ),
+        }
+    }
+
+    impl<'a> UnaryOp<'a> for Arc<Function> {
         fn eval_unary(
             &self,
-            _ctx: &super::context::Context,
-            _right: crate::value::Value,
-        ) -> Value {
-            todo!()
+            ctx: &mut Context<'a>,
+            right: Value,
+        ) -> Result<Value, Error> {
+            self.call(ctx, vec![right])
         }
     }
-    impl<'a> BinaryOp<'a> for &'a Function {
+
+    impl<'a> BinaryOp<'a> for Arc<Function> {
         fn eval_binary(
             &self,
-            _ctx: &super::context::Context,
-            _right: Value,
-            _left: Value,
-        ) -> Value {
-            todo!()
+            ctx: &mut Context<'a>,
+            left: Value,
+            right: Value,
+        ) -> Result<Value, Error> {
+            self.call(ctx, vec![left, right])
         }
     }
 }
@@ -1,8 +1,12 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{borrow::Cow, collections::HashMap, str::FromStr, sync::Arc};
+
+use rand::{rngs::StdRng, SeedableRng};
 
 use crate::{
     config::Config,
+    error::{errorf, Error},
     value::{
+        bigfloat::BigFloat,
         context::{expr::Expr, BinaryOp, UnaryOp},
         eval::{binary::BinaryBuiltin, product, reduce, scan, unary},
         Value,
@@ -31,8 +35,9 @@ impl OpDef {
 /// and operators.
 pub struct Context<'conf> {
     /// config is the configuration state used for evaluation, printing, etc.
-    /// Accessed through the [config] method.
-    config: &'conf Config,
+    /// Accessed through the [config] method. It is borrowed until a special
+    /// command changes it.
+    config: Cow<'conf, Config>,
 
     /// size of each stack frame on the call stack
     frame_sizes: Vec<usize>,
@@ -41,11 +46,11 @@ pub struct Context<'conf> {
 
     ///  `unary_fn` maps the names of unary functions (ops) to their
     ///  implemenations.
-    pub(crate) unary_fn: HashMap<String, Function>,
+    pub(crate) unary_fn: HashMap<String, Arc<Function>>,
 
     ///  `binary_fn` maps the names of binary functions (ops) to their
     ///  implemenations.
-    pub(crate) binary_fn: HashMap<String, Function>,
+    pub(crate) binary_fn: HashMap<String, Arc<Function>>,
 
     /// defs is a list of defined ops, in time order. it is used when saving the
     /// `Context` to a file.
@@ -53,12 +58,24 @@ pub struct Context<'conf> {
 
     /// names of variables declared in the currently-being-parsed function
     variables: Vec<String>,
+
+    /// the source of random numbers for `?`, seeded from the configuration so
+    /// that a session can be repeated
+    rng: StdRng,
 }
 
 impl<'a> Context<'a> {
     /// returns a new execution context: the stack and variables, plus the
     /// execution configuration.
     pub fn new(config: &'a Config) -> Self {
+        let mut c = Self::empty(Cow::Borrowed(config));
+        c.set_constants();
+        c
+    }
+
+    /// returns a context with no variables, not even the constants
+    fn empty(config: Cow<'a, Config>) -> Self {
+        let seed = config.seed();
         Self {
             config,
             frame_sizes: Vec::new(),
@@ -68,29 +85,92 @@ impl<'a> Context<'a> {
             binary_fn: HashMap::new(),
             defs: Vec::new(),
             variables: Vec::new(),
+            rng: StdRng::seed_from_u64(seed),
         }
     }
 
+    /// moves the state of the context out into a new context with the same
+    /// configuration, leaving this one empty until [`Context::restore`] puts
+    /// it back. This lets the state be used behind a shorter-lived lock, as
+    /// when the unary `ivy` runs a parser over its argument.
+    pub(crate) fn take(&mut self) -> Self {
+        std::mem::replace(self, Self::empty(self.config.clone()))
+    }
+
+    /// moves the state of `other` back into this context, along with its
+    /// configuration if a special command changed it
+    pub(crate) fn restore(&mut self, other: Context) {
+        let Context {
+            config: other_config,
+            frame_sizes,
+            stack,
+            globals,
+            unary_fn,
+            binary_fn,
+            defs,
+            variables,
+            rng,
+        } = other;
+        let config = match other_config {
+            Cow::Owned(c) => Cow::Owned(c),
+            Cow::Borrowed(_) => self.config.clone(),
+        };
+        *self = Self {
+            config,
+            frame_sizes,
+            stack,
+            globals,
+            unary_fn,
+            binary_fn,
+            defs,
+            variables,
+            rng,
+        };
+    }
+
     pub fn config(&self) -> &Config {
-        self.config
+        &self.config
+    }
+
+    /// returns the configuration for changing, copying it first if it is
+    /// still borrowed
+    pub fn config_mut(&mut self) -> &mut Config {
+        self.config.to_mut()
+    }
+
+    /// sets the seed in the configuration and restarts the random numbers
+    /// from it
+    pub fn set_seed(&mut self, seed: u64) {
+        self.config_mut().set_seed(seed);
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    /// returns the random number generator used by `?`
+    pub(crate) fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
     }
 
     /// re-assigns the fundamental constant values
     pub fn set_constants(&mut self) {
-        self.assign_global("e", Value::Float(std::f64::consts::E));
-        self.assign_global("pi", Value::Float(std::f64::consts::PI));
+        let prec = self.config.float_prec();
+        self.assign_global("e", Value::Float(BigFloat::e(prec)));
+        self.assign_global("pi", Value::Float(BigFloat::pi(prec)));
     }
 
     /// returns the value of a global symbol, or None if the symbol is not
     /// defined globally
-    pub fn global(&self, name: String) -> Option<&Value> {
-        self.globals.get(&name)
+    pub fn global(&self, name: &str) -> Option<&Value> {
+        self.globals.get(name)
     }
 
-    /// returns the value of the local variable with index i
-    pub fn local(&self, i: usize) -> &Value {
+    /// returns the value of the local variable with index i, or None if it
+    /// has not been assigned
+    pub fn local(&self, i: usize) -> Option<&Value> {
         let l = self.stack.len();
-        &self.stack[l - i]
+        match &self.stack[l - i] {
+            Value::None => None,
+            v => Some(v),
+        }
     }
 
     /// assigns the local variable with the given index the value.
@@ -106,28 +186,44 @@ impl<'a> Context<'a> {
         self.globals.insert(name.to_owned(), value);
     }
 
-    /// push pushes a new local frame onto the context stack
-    #[allow(unused)]
-    fn push(&mut self, fun: Function) {
+    /// push pushes a new local frame for `fun` onto the context stack. It
+    /// fails if that would make the stack, counting the `tail_calls` made in
+    /// place of nested ones, deeper than the configured maximum.
+    pub(crate) fn push(
+        &mut self,
+        fun: &Function,
+        tail_calls: usize,
+    ) -> Result<(), Error> {
+        if self.frame_sizes.len() + tail_calls >= self.config.max_stack() {
+            errorf!(
+                "stack overflow calling `{}`: more than {} nested calls",
+                fun.name(),
+                self.config.max_stack()
+            );
+        }
         let n = self.stack.len();
         let lfun = fun.locals.len();
         self.frame_sizes.push(lfun);
         self.stack.resize_with(n + lfun, Value::default);
+        Ok(())
     }
 
     /// pop pops the top frame from the stack
-    #[allow(unused)]
-    fn pop(&mut self) {
-        self.frame_sizes.pop();
-        self.stack.pop();
+    pub(crate) fn pop(&mut self) {
+        let n = self.frame_sizes.pop().unwrap_or(0);
+        self.stack.truncate(self.stack.len() - n);
     }
 
-    /// eval evaluates a list of expressions
-    pub fn eval(&self, exprs: Vec<Expr>) -> Vec<Value> {
+    /// eval evaluates a list of expressions, stopping at the first error
+    pub fn eval(&mut self, exprs: &[Expr]) -> Result<Vec<Value>, Error> {
         exprs.iter().map(|e| e.eval(self)).collect()
     }
 
-    pub fn eval_unary(&'a self, op: &str, right: Value) -> Value {
+    pub fn eval_unary(
+        &mut self,
+        op: &str,
+        right: Value,
+    ) -> Result<Value, Error> {
         let l = op.len();
         if l > 1 {
             let opi = op.chars().last().unwrap();
@@ -138,15 +234,15 @@ impl<'a> Context<'a> {
             }
         }
         let Some(fun) = self.unary(op) else {
-	    panic!("unary `{op}` not implemented")
-	};
+            errorf!("unary `{op}` not implemented")
+        };
         fun.eval_unary(self, right)
     }
 
     /// return the `UnaryOp` represented by `op`
-    pub fn unary(&'a self, op: &str) -> Option<Box<dyn UnaryOp<'a> + 'a>> {
+    pub fn unary(&self, op: &str) -> Option<Box<dyn UnaryOp<'a>>> {
         if let Some(user_fun) = self.unary_fn.get(op) {
-            return Some(Box::new(user_fun));
+            return Some(Box::new(user_fun.clone()));
         }
         if let Ok(builtin) = unary::UnaryBuiltin::from_str(op) {
             return Some(Box::new(builtin));
@@ -157,25 +253,30 @@ impl<'a> Context<'a> {
     /// check if op has been defined by the user
     pub fn user_defined(&self, op: &str, is_binary: bool) -> bool {
         if is_binary {
-            self.binary_fn.get(op).is_some()
+            self.binary_fn.contains_key(op)
         } else {
-            self.unary_fn.get(op).is_some()
+            self.unary_fn.contains_key(op)
         }
     }
 
-    pub fn eval_binary(&'a self, left: Value, op: &str, right: Value) -> Value {
+    pub fn eval_binary(
+        &mut self,
+        left: Value,
+        op: &str,
+        right: Value,
+    ) -> Result<Value, Error> {
         if op.contains('.') {
             return product(self, left, op, right);
         }
         let Some(fun) = self.binary(op) else {
-	    panic!("binary `{op}` not implemented");
-	};
+            errorf!("binary `{op}` not implemented");
+        };
         fun.eval_binary(self, left, right)
     }
 
-    pub fn binary(&'a self, op: &str) -> Option<Box<dyn BinaryOp<'a> + 'a>> {
+    pub fn binary(&self, op: &str) -> Option<Box<dyn BinaryOp<'a>>> {
         if let Some(user_fun) = self.binary_fn.get(op) {
-            return Some(Box::new(user_fun));
+            return Some(Box::new(user_fun.clone()));
         }
         if let Ok(builtin) = BinaryBuiltin::from_str(op) {
             return Some(Box::new(builtin));
@@ -186,15 +287,15 @@ impl<'a> Context<'a> {
     /// Define defines the function and installs it. It also performs some error
     /// checking and adds the function to the sequencing information used by the
     /// save method.
-    pub fn define(&mut self, fun: Function) {
+    pub fn define(&mut self, fun: Function) -> Result<(), Error> {
         let name = fun.name();
         let nname = name.to_owned();
-        self.no_var(name);
+        self.no_var(name)?;
         let fib = fun.is_binary;
         if fun.is_binary {
-            self.binary_fn.insert(name.to_owned(), fun);
+            self.binary_fn.insert(name.to_owned(), Arc::new(fun));
         } else {
-            self.unary_fn.insert(name.to_owned(), fun);
+            self.unary_fn.insert(name.to_owned(), Arc::new(fun));
         }
 
         // update the sequence of definitions. first, if it's last (a very
@@ -202,7 +303,7 @@ impl<'a> Context<'a> {
         if !self.defs.is_empty() {
             let last = self.defs.last().unwrap();
             if last.name == nname && last.is_binary == fib {
-                return;
+                return Ok(());
             }
         }
 
@@ -215,7 +316,54 @@ impl<'a> Context<'a> {
             self.defs.remove(i);
         }
 
-        self.defs.push(OpDef::new(nname, fib))
+        self.defs.push(OpDef::new(nname, fib));
+        Ok(())
+    }
+
+    /// returns the user-defined ops in the order in which they were defined
+    pub(crate) fn ops(&self) -> impl Iterator<Item = &Function> {
+        self.defs.iter().filter_map(|def| {
+            let map = if def.is_binary {
+                &self.binary_fn
+            } else {
+                &self.unary_fn
+            };
+            map.get(&def.name).map(Arc::as_ref)
+        })
+    }
+
+    /// returns the global variables, sorted by name
+    pub(crate) fn globals(&self) -> Vec<(&str, &Value)> {
+        let mut globals: Vec<_> =
+            self.globals.iter().map(|(k, v)| (k.as_str(), v)).collect();
+        globals.sort_by_key(|&(name, _)| name);
+        globals
+    }
+
+    /// reinstates `prev` as the definition of the op `name` after a failed
+    /// definition, or forgets the op entirely if it was new
+    pub(crate) fn undefine(
+        &mut self,
+        name: &str,
+        is_binary: bool,
+        prev: Option<Arc<Function>>,
+    ) {
+        let install_map = if is_binary {
+            &mut self.binary_fn
+        } else {
+            &mut self.unary_fn
+        };
+        match prev {
+            Some(fun) => {
+                install_map.insert(name.to_owned(), fun);
+            }
+            None => {
+                install_map.remove(name);
+                self.defs.retain(|def| {
+                    def.name != name || def.is_binary != is_binary
+                });
+            }
+        }
     }
 
     /// guarantees that there is no global variable with that name, preventing
@@ -224,37 +372,35 @@ impl<'a> Context<'a> {
     /// one can clear a variable before defining a symbol. A cleared variable is
     /// removed from the global symbol table. `no_var` also prevents defining
     /// builtin variables as ops.
-    fn no_var(&mut self, name: &str) {
+    fn no_var(&mut self, name: &str) -> Result<(), Error> {
         // cannot redefine these
         if name == "_" || name == "pi" || name == "e" {
-            panic!("can't define op with name `{name}`");
+            errorf!("can't define op with name `{name}`");
         }
-        if let Some(sym) = self.globals.get(name) {
-            if let Value::Int(i) = sym {
-                if *i == 0 {
-                    self.globals.remove(name);
-                }
+        match self.globals.get(name) {
+            Some(Value::Int(0)) => {
+                self.globals.remove(name);
+                Ok(())
             }
-        } else {
-            return;
-        }
-        panic!(
-            "cannot define op `{name}`; it is a variable \
+            Some(_) => errorf!(
+                "cannot define op `{name}`; it is a variable \
 		({name} = 0 to clear)"
-        );
+            ),
+            None => Ok(()),
+        }
     }
 
     /// `no_op` is the dual of noVar. It also checks for assignment to builtins.
     /// It just errors out if there is a conflict.
-    #[allow(unused)]
-    fn no_op(&mut self, name: &str) {
+    pub(crate) fn no_op(&self, name: &str) -> Result<(), Error> {
         if name == "pi" || name == "e" {
-            panic!("can't reassign `{name}`");
+            errorf!("can't reassign `{name}`");
         }
         if self.unary_fn.contains_key(name) || self.binary_fn.contains_key(name)
         {
-            panic!("cannot define variable `{name}`, it is an op");
+            errorf!("cannot define variable `{name}`, it is an op");
         }
+        Ok(())
     }
 
     /// `declare` makes the name a variable while parsing the next function.
@@ -272,4 +418,13 @@ impl<'a> Context<'a> {
     pub fn is_variable(&self, op: &str) -> bool {
         self.variables.iter().any(|var| *var == op)
     }
+
+    /// returns the local index of the variable `name` in the function being
+    /// parsed, or 0 if it is not declared there and so is global
+    pub fn local_index(&self, name: &str) -> usize {
+        self.variables
+            .iter()
+            .position(|var| var == name)
+            .map_or(0, |i| i + 1)
+    }
 }
@@ -1,6 +1,6 @@
-#![feature(iter_collect_into, let_chains)]
-
 pub mod config;
+pub mod edit;
+pub mod error;
 pub mod exec;
 pub mod parse;
 pub mod run;
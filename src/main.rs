@@ -1,24 +1,223 @@
-use std::sync::RwLock;
+use std::{
+    fmt::Debug,
+    io::{IsTerminal, Read},
+    sync::RwLock,
+};
 
 use algae::{
-    config::Config, exec::context::Context, parse::Parser, scan::Scanner,
+    config::{Config, Options},
+    edit::Editor,
+    exec::context::Context,
+    parse::Parser,
+    scan::Scanner,
+    value::format,
 };
 
+/// the size of the stack the interpreter runs on. Calls to user-defined ops
+/// nest on it, so it must be deep enough for `Config::max_stack` calls; only
+/// the pages actually used are ever touched.
+const STACK_SIZE: usize = 1 << 30;
+
+const USAGE: &str = "\
+usage: algae [options] [file ...]
+
+Runs each -e expression and file in order, then exits. With none, or with
+-i, reads from standard input, with line editing if it is a terminal.
+
+options:
+  -e expr         evaluate expr
+  -f file         run file; - is standard input
+  -i              run interactively after the expressions and files
+  -s, --stop      stop at the first error
+  --prompt str    the interactive prompt
+  --format fmt    the printf-style format for printing numbers
+  --origin n      the index origin
+  --prec n        the precision of floats, in bits
+  --ibase n       the base in which numbers are read
+  --obase n       the base in which numbers are printed
+  --seed n        the seed for random numbers
+  --maxbits n     the maximum number of bits in an integer, or 0
+  --maxdigits n   the most digits printed before scientific notation, or 0
+  --maxstack n    the maximum depth of calls to ops
+  -h, --help      print this message";
+
 fn main() {
-    // TODO take config options from flags. make `new` take the same options as
-    // flags
-    let conf = Config::default();
+    let interp = std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run)
+        .expect("failed to start interpreter thread");
+    let code = interp.join().unwrap_or(2);
+    std::process::exit(code);
+}
+
+/// an input to run, in the order given on the command line
+#[derive(Debug, PartialEq)]
+enum Input {
+    Expr(String),
+    File(String),
+}
+
+/// the command line
+#[derive(Debug, Default)]
+struct Args {
+    options: Options,
+    inputs: Vec<Input>,
+    interactive: bool,
+    stop: bool,
+    help: bool,
+}
+
+impl Args {
+    /// parses the command line, not including the program name. Long options
+    /// may start with one dash or two, and take their value either as the
+    /// next argument or after an `=`.
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Args::default();
+        let opts = &mut parsed.options;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let Some(flag) = arg.strip_prefix('-').filter(|f| !f.is_empty())
+            else {
+                parsed.inputs.push(Input::File(arg));
+                continue;
+            };
+            let flag = flag.strip_prefix('-').unwrap_or(flag);
+            let (flag, inline) = match flag.split_once('=') {
+                Some((flag, value)) => (flag, Some(value.to_owned())),
+                None => (flag, None),
+            };
+            match flag {
+                "i" => parsed.interactive = true,
+                "s" | "stop" => parsed.stop = true,
+                "h" | "help" => parsed.help = true,
+                _ => {
+                    let Some(value) = inline.or_else(|| args.next()) else {
+                        return Err(format!("flag -{flag} needs a value"));
+                    };
+                    let number = || {
+                        value.parse::<usize>().map_err(|_| {
+                            format!("flag -{flag}: bad number {value:?}")
+                        })
+                    };
+                    match flag {
+                        "e" => parsed.inputs.push(Input::Expr(value)),
+                        "f" => parsed.inputs.push(Input::File(value)),
+                        "prompt" => opts.prompt = value,
+                        "format" => opts.format = value,
+                        "origin" => opts.origin = number()?,
+                        "prec" => opts.float_prec = number()?,
+                        "ibase" => opts.input_base = number()?,
+                        "obase" => opts.output_base = number()?,
+                        "seed" => {
+                            opts.seed = value.parse().map_err(|_| {
+                                format!("flag -seed: bad number {value:?}")
+                            })?
+                        }
+                        "maxbits" => opts.max_bits = number()?,
+                        "maxdigits" => opts.max_digits = number()?,
+                        "maxstack" => opts.max_stack = number()?,
+                        _ => return Err(format!("unknown flag -{flag}")),
+                    }
+                }
+            }
+        }
+        for base in [opts.input_base, opts.output_base] {
+            if base == 1 || base > 36 {
+                return Err(format!("illegal base {base}"));
+            }
+        }
+        if !opts.format.is_empty() {
+            format::verb(&opts.format).map_err(|e| e.to_string())?;
+        }
+        if opts.float_prec == 0 {
+            return Err(String::from("illegal prec 0"));
+        }
+        Ok(parsed)
+    }
+}
+
+/// runs the interpreter, returning the process exit status
+fn run() -> i32 {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(msg) => {
+            eprintln!("algae: {msg}\n{USAGE}");
+            return 2;
+        }
+    };
+    if args.help {
+        println!("{USAGE}");
+        return 0;
+    }
+    let conf = Config::new(args.options);
     let context = RwLock::new(Context::new(&conf));
-    let mut args = std::env::args();
-    if let Some(infile) = args.nth(1) {
-        let f = std::fs::File::open(infile).expect("failed to open file");
-        let scanner = Scanner::new(&context, "file", Box::new(f));
-        let mut parser = Parser::new("<stdin>", scanner, &context);
-        while parser.run(&conf, &context, false).is_err() {}
+    let stop = args.stop;
+    let mut ok = true;
+    for input in &args.inputs {
+        ok &= match input {
+            Input::Expr(expr) => {
+                run_input(&context, "-e", expr.as_bytes(), stop)
+            }
+            Input::File(file) if file == "-" => {
+                run_input(&context, "<stdin>", std::io::stdin(), stop)
+            }
+            Input::File(file) => match std::fs::File::open(file) {
+                Ok(f) => run_input(&context, file, f, stop),
+                Err(e) => {
+                    eprintln!("{file}: {e}");
+                    false
+                }
+            },
+        };
+        if stop && !ok {
+            return 1;
+        }
+    }
+    if args.interactive || args.inputs.is_empty() {
+        if std::io::stdin().is_terminal() {
+            // errors typed at the prompt are not the program's failure
+            run_input(&context, "<stdin>", Editor::stdio(&context), false);
+        } else {
+            ok &= run_input(&context, "<stdin>", std::io::stdin(), stop);
+        }
+    }
+    if ok {
+        0
     } else {
-        // interactive
-        let scanner = Scanner::new(&context, "<stdin>", std::io::stdin());
-        let mut parser = Parser::new("<stdin>", scanner, &context);
-        while parser.run(&conf, &context, true).is_err() {}
+        1
     }
 }
+
+/// runs the program read from `r`, printing its values and reporting errors
+/// as it goes, or stopping at the first error if `stop` is set. Returns
+/// whether every line run succeeded.
+fn run_input<'a, R: Read + Debug>(
+    context: &'a RwLock<Context<'a>>,
+    name: &str,
+    r: R,
+    stop: bool,
+) -> bool {
+    let scanner = Scanner::new(context, name, r);
+    let mut parser = Parser::new(name, scanner, context);
+    parser.run_all(context, false, stop)
+}
+
+#[test]
+fn flags() {
+    let args = |s: &str| Args::parse(s.split(' ').map(String::from));
+    let parsed = args("-e 1+2 --prec=64 -origin 0 -f a.ivy b.ivy -i").unwrap();
+    assert_eq!(parsed.options.float_prec, 64);
+    assert_eq!(parsed.options.origin, 0);
+    assert!(parsed.interactive);
+    assert!(!parsed.stop);
+    assert!(args("--stop b.ivy").unwrap().stop);
+    let inputs = [
+        Input::Expr(String::from("1+2")),
+        Input::File(String::from("a.ivy")),
+        Input::File(String::from("b.ivy")),
+    ];
+    assert_eq!(parsed.inputs, inputs);
+    assert!(args("--ibase 37").is_err());
+    assert!(args("--maxbits").is_err());
+    assert!(args("--bogus 1").is_err());
+}
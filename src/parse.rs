@@ -1,15 +1,10 @@
-use std::{
-    collections::HashMap,
-    error::Error,
-    fmt::{Debug, Display},
-    io::Read,
-    sync::RwLock,
-};
+use std::{fmt::Debug, io::Read, sync::RwLock};
 
 use crate::{
+    error::Error,
     exec::{context::Context, function::Function},
     scan::{Scanner, Token, Type},
-    value::{context::expr::Expr, parse, parse_string, Value},
+    value::{context::expr::Expr, format, parse, parse_string, Value},
 };
 
 #[allow(unused)]
@@ -22,24 +17,53 @@ pub struct Parser<'a, R: Read> {
     context: &'a RwLock<Context<'a>>,
 }
 
-#[derive(Debug, Clone)]
-pub struct ParseError;
-
-impl Error for ParseError {}
-
-impl Display for ParseError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
-    }
-}
-
+/// discards the rest of the line and returns early with a parse error built
+/// from the format arguments
 macro_rules! errorf {
     ($parser: ident, $($args:tt)*) => {
 	$parser.tokens.clear();
-	panic!($($args)*);
+	return Err($crate::error::Error::parse(format!($($args)*)));
     }
 }
 
+mod save;
+
+/// the names of the special commands, which follow a `)`
+pub const SPECIALS: &[&str] = &[
+    "base",
+    "ibase",
+    "obase",
+    "format",
+    "prompt",
+    "origin",
+    "prec",
+    "seed",
+    "maxbits",
+    "maxdigits",
+    "maxstack",
+    "save",
+    "get",
+    "help",
+];
+
+/// the text printed by `)help`
+const HELP: &str = "\
+special commands, which print the setting when given no value:
+  )base n        the input and output base
+  )ibase n       the input base
+  )obase n       the output base
+  )format 'fmt'  the printf-style format for printing numbers
+  )prompt 'str'  the interactive prompt
+  )origin n      the index origin
+  )prec n        the precision of floats, in bits
+  )seed n        the seed for random numbers
+  )maxbits n     the maximum number of bits in an integer, or 0
+  )maxdigits n   the most digits printed before scientific notation, or 0
+  )maxstack n    the maximum depth of calls to ops
+  )save 'file'   save the session to file, by default save.ivy
+  )get 'file'    run file, by default save.ivy
+  )help          print this message";
+
 impl<'a, R: Read + Debug> Parser<'a, R> {
     pub fn new(
         filename: &str,
@@ -56,36 +80,50 @@ impl<'a, R: Read + Debug> Parser<'a, R> {
         }
     }
 
-    /// Line reads a line of input and returns the values it evaluates. An empty
-    /// returned Vec means there were no values. An Error means the line was
-    /// invalid.
+    /// Line reads a line of input and returns the expressions it contains. An
+    /// empty returned Vec means there were no values, and `None` means the
+    /// input is exhausted. An Error means the line was invalid; it carries the
+    /// file name and line number, and the rest of the line is discarded.
     ///
     /// Line
     ///
     /// ) special command '\n'
     /// def function defintion
     /// expressionList '\n'
-    pub fn line(&mut self) -> Result<Vec<Expr>, ParseError> {
-        if !self.read_tokens_to_newline() {
-            return Err(ParseError);
+    pub fn line(&mut self) -> Result<Option<Vec<Expr>>, Error> {
+        self.parse_line().map_err(|e| {
+            self.tokens.clear();
+            e.at(&self.filename, self.line_num)
+        })
+    }
+
+    fn parse_line(&mut self) -> Result<Option<Vec<Expr>>, Error> {
+        if !self.read_tokens_to_newline()? {
+            return Ok(None);
         }
         let exprs = Vec::new();
         let tok = self.peek();
         match tok.typ {
-            Type::Eof => Ok(exprs),
+            Type::Eof => Ok(Some(exprs)),
             Type::RightParen => {
-                self.special();
+                self.special()?;
                 self.context.write().unwrap().set_constants();
-                Ok(exprs)
+                Ok(Some(exprs))
             }
             Type::Op => {
-                self.function_defn();
-                Ok(exprs)
+                self.function_defn()?;
+                Ok(Some(exprs))
             }
-            _ => self.expression_list(),
+            _ => self.expression_list().map(Some),
         }
     }
 
+    /// returns the file name and line number of the most recent token, for
+    /// locating errors found after parsing
+    pub fn location(&self) -> (&str, usize) {
+        (&self.filename, self.line_num)
+    }
+
     fn peek(&self) -> Token {
         if self.tokens.is_empty() {
             return Token::new(Type::Eof, 0, String::new());
@@ -93,32 +131,144 @@ impl<'a, R: Read + Debug> Parser<'a, R> {
         self.tokens[0].clone()
     }
 
-    fn read_tokens_to_newline(&mut self) -> bool {
+    /// reads the tokens of the next line into `self.tokens`, returning false if
+    /// the input is exhausted
+    fn read_tokens_to_newline(&mut self) -> Result<bool, Error> {
         self.tokens.clear();
         loop {
             let tok = self.scanner.next_token();
             match tok.typ {
-                Type::Eof => return !self.tokens.is_empty(),
+                Type::Eof => return Ok(!self.tokens.is_empty()),
                 Type::Error => {
-                    errorf!(self, "{:#?}", tok);
+                    let msg = tok.text.clone();
+                    self.line_num = tok.line;
+                    errorf!(self, "{msg}");
                 }
-                Type::Newline => return true,
+                Type::Newline => return Ok(true),
                 _ => {}
             }
+            self.line_num = tok.line;
             self.tokens.push(tok.clone());
         }
     }
 
-    fn special(&mut self) {
-        self.need(Type::RightParen);
-        // TODO should be changing base here
+    /// special command
+    ///
+    /// ')' name <eol>
+    /// ')' name value <eol>
+    ///
+    /// Without a value the command prints the setting, and with one it changes
+    /// it. Numbers here are always decimal, whatever the input base. `)save`
+    /// and `)get` take a quoted file name, by default save.ivy.
+    fn special(&mut self) -> Result<(), Error> {
+        self.need(Type::RightParen)?;
+        let tok = self.next()?;
+        if tok.typ != Type::Identifier {
+            errorf!(self, "expected special command, found {tok}");
+        }
+        let arg = self.next()?;
+        let end = self.next()?;
+        if !end.typ.is_eof() {
+            errorf!(self, "unexpected {end}");
+        }
+        let name = tok.text.as_str();
+        if let "save" | "get" = name {
+            let file = match arg.typ {
+                Type::Eof => String::from("save.ivy"),
+                Type::String => parse_string(arg.text)?,
+                _ => {
+                    errorf!(self, "){name}: expected file name, found {arg}");
+                }
+            };
+            return match name {
+                "save" => self.save(&file),
+                _ => self.get(&file),
+            };
+        }
+        let context = self.context;
+        let mut context = context.write().unwrap();
+        if arg.typ.is_eof() {
+            let conf = context.config();
+            let decimal = |base| if base == 0 { 10 } else { base };
+            let (ibase, obase) =
+                (decimal(conf.input_base()), decimal(conf.output_base()));
+            match name {
+                "base" => println!("ibase\t{ibase}\nobase\t{obase}"),
+                "ibase" => println!("{ibase}"),
+                "obase" => println!("{obase}"),
+                "format" => println!("{:?}", conf.format()),
+                "prompt" => println!("{:?}", conf.prompt()),
+                "origin" => println!("{}", conf.origin()),
+                "prec" => println!("{}", conf.float_prec()),
+                "seed" => println!("{}", conf.seed()),
+                "maxbits" => println!("{}", conf.max_bits()),
+                "maxdigits" => println!("{}", conf.max_digits()),
+                "maxstack" => println!("{}", conf.max_stack()),
+                "help" => println!("{HELP}"),
+                _ => {
+                    errorf!(self, "unknown special command ){name}");
+                }
+            }
+            return Ok(());
+        }
+        if let "format" | "prompt" = name {
+            if arg.typ != Type::String {
+                errorf!(self, "){name}: expected string, found {arg}");
+            }
+            let text = parse_string(arg.text)?;
+            if name == "format" && !text.is_empty() {
+                if let Err(e) = format::verb(&text) {
+                    errorf!(self, "{e}");
+                }
+            }
+            match name {
+                "format" => context.config_mut().set_format(text),
+                _ => context.config_mut().set_prompt(text),
+            }
+            return Ok(());
+        }
+        let n = match arg.text.parse::<u64>() {
+            Ok(n) if arg.typ == Type::Number => n,
+            _ => {
+                errorf!(self, "){name}: bad number {arg}");
+            }
+        };
+        let conf = context.config_mut();
+        match name {
+            "base" | "ibase" | "obase" => {
+                if n == 1 || n > 36 {
+                    errorf!(self, "illegal base {n}");
+                }
+                if name != "obase" {
+                    conf.set_input_base(n as usize);
+                }
+                if name != "ibase" {
+                    conf.set_output_base(n as usize);
+                }
+            }
+            "origin" => conf.set_origin(n as usize),
+            "prec" => {
+                if n == 0 {
+                    errorf!(self, "illegal prec {n}");
+                }
+                conf.set_float_prec(n as usize);
+            }
+            "seed" => context.set_seed(n),
+            "maxbits" => conf.set_max_bits(n as usize),
+            "maxdigits" => conf.set_max_digits(n as usize),
+            "maxstack" => conf.set_max_stack(n as usize),
+            _ => {
+                errorf!(self, "unknown special command ){name}");
+            }
+        }
+        Ok(())
     }
 
     /// expressionList:
     /// statementList <eol>
-    fn expression_list(&mut self) -> Result<Vec<Expr>, ParseError> {
+    fn expression_list(&mut self) -> Result<Vec<Expr>, Error> {
         let exprs = self.statement_list()?;
-        let tok = self.next();
+        let tok = self.next()?;
         if !tok.typ.is_eof() {
             errorf!(self, "unexpected {tok}");
         }
@@ -126,16 +276,16 @@ impl<'a, R: Read + Debug> Parser<'a, R> {
         Ok(exprs)
     }
 
-    fn need(&mut self, typ: Type) -> Token {
-        let tok = self.next();
+    fn need(&mut self, typ: Type) -> Result<Token, Error> {
+        let tok = self.next()?;
         // TODO take multiple typ and loop over it
         if tok.typ == typ {
-            return tok;
+            return Ok(tok);
         }
-        errorf!(self, "{}", tok);
+        errorf!(self, "expected {typ:?}, found {tok}");
     }
 
-    fn next(&mut self) -> Token {
+    fn next(&mut self) -> Result<Token, Error> {
         let tok = self.peek();
         if tok.typ != Type::Eof {
             // go code says self.tokens[1..], not sure if it's better to call
@@ -146,24 +296,30 @@ impl<'a, R: Read + Debug> Parser<'a, R> {
         if tok.typ == Type::Error {
             errorf!(self, "{}", tok);
         }
-        tok
+        Ok(tok)
     }
 
-    #[allow(unused)]
-    fn function_defn(&mut self) {
-        self.need(Type::Op);
+    /// function definition
+    ///
+    /// "op" name arg '=' statements <eol>
+    /// "op" arg name arg '=' statements <eol>
+    ///
+    /// statements:
+    /// expressionList
+    /// '\n' (expressionList '\n')+ '\n' # multi-line, ending with blank line
+    fn function_defn(&mut self) -> Result<(), Error> {
+        self.need(Type::Op)?;
         let mut fun = Function::default();
         // two identifiers means op arg
         // three identifiers means arg op arg
         let mut idents = vec![
-            self.need(Type::Identifier).text,
-            self.need(Type::Identifier).text,
+            self.need(Type::Identifier)?.text,
+            self.need(Type::Identifier)?.text,
         ];
         if self.peek().typ.is_identifier() {
-            idents.push(self.next().text);
+            idents.push(self.next()?.text);
         }
-        let tok = self.next();
-        let mut install_map = HashMap::new();
+        let tok = self.next()?;
         if idents.len() == 3 {
             if idents[1] == "o" {
                 errorf!(self, "o is not a valid name for a binary operator");
@@ -172,42 +328,118 @@ impl<'a, R: Read + Debug> Parser<'a, R> {
             fun.left = idents[0].clone();
             fun.name = idents[1].clone();
             fun.right = idents[2].clone();
-            self.context.write().unwrap().declare(&fun.left);
-            self.context.write().unwrap().declare(&fun.right);
-            install_map = self.context.read().unwrap().binary_fn.clone();
         } else {
             fun.name = idents[0].clone();
             fun.right = idents[1].clone();
-            self.context.write().unwrap().declare(&fun.right);
-            install_map = self.context.read().unwrap().unary_fn.clone();
         }
         if fun.name == fun.left || fun.name == fun.right {
             errorf!(self, "argument name `{}` is function name", fun.name);
         }
-        // define it but prepare to undefine if there's trouble
-        let name = fun.name.clone();
-        self.context.write().unwrap().define(fun);
-        let mut succeeded = false;
-        let prev_defn = install_map.get(&name);
+        if tok.typ != Type::Assign {
+            errorf!(self, "expected = after function definition, found {tok}");
+        }
+        // define it but prepare to undefine if there's trouble. It must be
+        // defined while parsing the body so that it can call itself.
+        let mut context = self.context.write().unwrap();
+        let install_map = if fun.is_binary {
+            &context.binary_fn
+        } else {
+            &context.unary_fn
+        };
+        let prev_defn = install_map.get(&fun.name).cloned();
+        context.define(fun.clone())?;
+        if fun.is_binary {
+            context.declare(&fun.left);
+        }
+        context.declare(&fun.right);
+        drop(context);
+
+        let res = self.function_body(&mut fun);
+        let mut context = self.context.write().unwrap();
+        context.forget_all();
+        match res {
+            Ok(()) => context.define(fun),
+            Err(e) => {
+                context.undefine(&fun.name, fun.is_binary, prev_defn);
+                Err(e)
+            }
+        }
+    }
+
+    /// parses the body of `fun`, either the rest of the line or the following
+    /// lines up to a blank one, and resolves its variables. The variables
+    /// assigned in the body are local to it, as are the arguments; all others
+    /// are global.
+    fn function_body(&mut self, fun: &mut Function) -> Result<(), Error> {
+        if self.peek().typ.is_eof() {
+            // multi-line
+            if !self.read_tokens_to_newline()? {
+                errorf!(self, "invalid function definition");
+            }
+            while !self.peek().typ.is_eof() {
+                fun.body.extend(self.statement_list()?);
+                let tok = self.peek();
+                if !tok.typ.is_eof() {
+                    errorf!(self, "unexpected {tok}");
+                }
+                if !self.read_tokens_to_newline()? {
+                    errorf!(self, "invalid function definition");
+                }
+            }
+        } else {
+            fun.body = self.expression_list()?;
+        }
+        if fun.body.is_empty() {
+            errorf!(self, "missing function body");
+        }
+
+        if fun.is_binary {
+            fun.locals.push(fun.left.clone());
+        }
+        fun.locals.push(fun.right.clone());
+        for e in &mut fun.body {
+            e.visit(&mut |e| {
+                if let Expr::Binary { binary } = e {
+                    if let Expr::VariableExpr { name, .. } = &binary.left {
+                        if binary.op == "=" && !fun.locals.contains(name) {
+                            fun.locals.push(name.clone());
+                        }
+                    }
+                }
+            });
+        }
+        for e in &mut fun.body {
+            e.visit(&mut |e| {
+                if let Expr::VariableExpr { name, local } = e {
+                    *local = fun
+                        .locals
+                        .iter()
+                        .position(|l| l == name)
+                        .map_or(0, |i| i + 1);
+                    if *local == 0 && !fun.globals.contains(name) {
+                        fun.globals.push(name.clone());
+                    }
+                }
+            });
+        }
+        Ok(())
     }
 
     /// statementList:
     ///    expr [':' expr] [';' statementList]
-    fn statement_list(&mut self) -> Result<Vec<Expr>, ParseError> {
-        let mut expr = self.expr();
+    fn statement_list(&mut self) -> Result<Vec<Expr>, Error> {
+        let mut expr = self.expr()?;
         if !expr.is_nil() && self.peek().typ == Type::Colon {
-            let tok = self.next();
-            expr = Expr::conditional(tok.text, expr, self.expr());
+            let tok = self.next()?;
+            expr = Expr::conditional(tok.text, expr, self.expr()?);
         }
         let mut exprs = Vec::new();
         if !expr.is_nil() {
             exprs.push(expr);
         }
         if self.peek().typ == Type::Semicolon {
-            self.next();
-            if let Ok(more) = self.statement_list() {
-                exprs.extend(more);
-            }
+            self.next()?;
+            exprs.extend(self.statement_list()?);
         }
         Ok(exprs)
     }
@@ -215,33 +447,36 @@ impl<'a, R: Read + Debug> Parser<'a, R> {
     /// expr
     ///    operand
     ///    operand binop expr
-    fn expr(&mut self) -> Expr {
-        let mut tok = self.next();
-        let expr = self.operand(tok, true);
+    fn expr(&mut self) -> Result<Expr, Error> {
+        let mut tok = self.next()?;
+        let expr = self.operand(tok, true)?;
         tok = self.peek();
         use Type::*;
         match tok.typ {
-            Eof | RightParen | RightBrack | Semicolon | Colon => return expr,
-            Identifier => {
-                if self.context.read().unwrap().defined_binary(&tok.text) {
-                    self.next();
-                    return Expr::binary(tok.text, expr, self.expr());
-                }
+            Eof | RightParen | RightBrack | Semicolon | Colon => {
+                return Ok(expr)
+            }
+            Identifier
+                if self.context.read().unwrap().defined_binary(&tok.text) =>
+            {
+                self.next()?;
+                return Ok(Expr::binary(tok.text, expr, self.expr()?));
             }
             Assign => {
-                self.next();
+                self.next()?;
                 match expr {
                     Expr::VariableExpr { .. } | Expr::Index { .. } => {
-                        return Expr::binary(tok.text, expr, self.expr());
+                        return Ok(Expr::binary(tok.text, expr, self.expr()?));
                     }
                     _ => {
-                        errorf!(self, "cannot assign to {:#?}", expr);
+                        let text = expr.prog_string();
+                        errorf!(self, "cannot assign to `{text}`");
                     }
                 }
             }
             Operator => {
-                self.next();
-                return Expr::binary(tok.text, expr, self.expr());
+                self.next()?;
+                return Ok(Expr::binary(tok.text, expr, self.expr()?));
             }
             _ => {}
         }
@@ -255,28 +490,28 @@ impl<'a, R: Read + Debug> Parser<'a, R> {
     ///    vector
     ///    operand [ Expr ]...
     ///    unop Expr
-    fn operand(&mut self, tok: Token, index_ok: bool) -> Expr {
+    fn operand(&mut self, tok: Token, index_ok: bool) -> Result<Expr, Error> {
         use Type::*;
         let mut expr = match tok.typ {
-            Operator => Expr::unary(tok.text, self.expr()),
+            Operator => Expr::unary(tok.text, self.expr()?),
             Identifier => {
                 if self.context.read().unwrap().defined_unary(&tok.text) {
-                    Expr::unary(tok.text, self.expr())
+                    Expr::unary(tok.text, self.expr()?)
                 } else {
-                    self.number_or_vector(tok)
+                    self.number_or_vector(tok)?
                 }
             }
             Number | Rational | Complex | String | LeftParen => {
-                self.number_or_vector(tok)
+                self.number_or_vector(tok)?
             }
             _ => {
                 errorf!(self, "unexpected {tok}");
             }
         };
         if index_ok {
-            expr = self.index(expr);
+            expr = self.index(expr)?;
         }
-        expr
+        Ok(expr)
     }
 
     // numberOrVector turns the token and what follows into a numeric Value,
@@ -286,8 +521,11 @@ impl<'a, R: Read + Debug> Parser<'a, R> {
     //	number
     //	string
     //	numberOrVector...
-    pub(crate) fn number_or_vector(&mut self, tok: Token) -> Expr {
-        let (mut expr, mut s) = self.number(tok);
+    pub(crate) fn number_or_vector(
+        &mut self,
+        tok: Token,
+    ) -> Result<Expr, Error> {
+        let (mut expr, mut s) = self.number(tok)?;
         use Type::*;
         let done = !matches!(
             self.peek().typ,
@@ -303,12 +541,19 @@ impl<'a, R: Read + Debug> Parser<'a, R> {
             loop {
                 let tok = self.peek();
                 match tok.typ {
-                    LeftParen | Identifier => {
-                        if self.context.read().unwrap().defined_op(&tok.text) {
+                    LeftParen | Identifier | Number | Rational | Complex
+                    | String => {
+                        if matches!(tok.typ, LeftParen | Identifier)
+                            && self
+                                .context
+                                .read()
+                                .unwrap()
+                                .defined_op(&tok.text)
+                        {
                             break;
                         }
-                        let n = self.next();
-                        (expr, s) = self.number(n);
+                        let n = self.next()?;
+                        (expr, s) = self.number(n)?;
                         if expr.is_nil() {
                             // must be a string
                             slice.extend(eval_string(s));
@@ -321,9 +566,9 @@ impl<'a, R: Read + Debug> Parser<'a, R> {
             }
         }
         if slice.len() == 1 {
-            return slice[0].clone();
+            return Ok(slice[0].clone());
         }
-        Expr::SliceExpr { exprs: slice }
+        Ok(Expr::SliceExpr { exprs: slice })
     }
 
     // index
@@ -331,22 +576,22 @@ impl<'a, R: Read + Debug> Parser<'a, R> {
     //	expr
     //	expr [ expr ]
     //	expr [ expr ] [ expr ] ....
-    pub(crate) fn index(&mut self, mut expr: Expr) -> Expr {
+    pub(crate) fn index(&mut self, mut expr: Expr) -> Result<Expr, Error> {
         while self.peek().typ == Type::LeftBrack {
-            self.next();
-            let list = self.index_list();
-            let tok = self.next();
+            self.next()?;
+            let list = self.index_list()?;
+            let tok = self.next()?;
             if tok.typ != Type::RightBrack {
                 errorf!(self, "expected right bracket, found {tok}");
             }
             expr = Expr::index(String::new(), expr, list);
         }
-        expr
+        Ok(expr)
     }
 
     // indexList
     //	[[expr] [';' [expr]] ...]
-    fn index_list(&mut self) -> Vec<Expr> {
+    fn index_list(&mut self) -> Result<Vec<Expr>, Error> {
         let mut list = Vec::new();
         // previous element contained an expression
         let mut seen = false;
@@ -358,17 +603,17 @@ impl<'a, R: Read + Debug> Parser<'a, R> {
                     if !seen {
                         list.push(Expr::Nil);
                     }
-                    return list;
+                    return Ok(list);
                 }
                 Semicolon => {
-                    self.next();
+                    self.next()?;
                     if !seen {
                         list.push(Expr::Nil);
                     }
                     seen = false;
                 }
                 _ => {
-                    list.push(self.expr());
+                    list.push(self.expr()?);
                     seen = true;
                 }
             }
@@ -382,22 +627,25 @@ impl<'a, R: Read + Debug> Parser<'a, R> {
     //	variable
     //	'(' Expr ')'
     // If the value is a string, value.Expr is nil.
-    pub(crate) fn number(&mut self, tok: Token) -> (Expr, String) {
+    pub(crate) fn number(
+        &mut self,
+        tok: Token,
+    ) -> Result<(Expr, String), Error> {
         let text = tok.text;
         let (expr, s) = match tok.typ {
-            Type::Identifier => (self.variable(text), String::new()),
-            Type::String => (Expr::Nil, parse_string(text)),
+            Type::Identifier => (self.variable(text)?, String::new()),
+            Type::String => (Expr::Nil, parse_string(text)?),
             Type::Number | Type::Rational | Type::Complex => {
                 match parse(self.context.read().unwrap().config(), &text) {
                     Ok(v) => (v.into(), String::new()),
                     Err(e) => {
-                        errorf!(self, "{text}: {:#?}", e);
+                        errorf!(self, "{text}: {}", e.msg());
                     }
                 }
             }
             Type::LeftParen => {
-                let expr = self.expr();
-                let tok = self.next();
+                let expr = self.expr()?;
+                let tok = self.next()?;
                 if tok.typ != Type::RightParen {
                     errorf!(self, "expected right paren, found {tok}");
                 }
@@ -405,14 +653,163 @@ impl<'a, R: Read + Debug> Parser<'a, R> {
             }
             _ => (Expr::Nil, String::new()),
         };
-        (expr, s)
+        Ok((expr, s))
     }
 
-    fn variable(&self, _text: String) -> Expr {
-        todo!()
+    /// returns the expression for the variable `name`, which is local if it is
+    /// declared in the function being defined and global otherwise
+    fn variable(&mut self, name: String) -> Result<Expr, Error> {
+        let local = self.context.read().unwrap().local_index(&name);
+        Ok(Expr::VariableExpr { name, local })
     }
 }
 
 fn eval_string(s: String) -> Vec<Expr> {
     s.chars().map(|c| Expr::Value(Value::Char(c))).collect()
 }
+
+/// returns a parser that reads `input` in `context`
+#[cfg(test)]
+pub(crate) fn test_parser<'a>(
+    context: &'a RwLock<Context<'a>>,
+    input: &'a str,
+) -> Parser<'a, &'a [u8]> {
+    let scanner = Scanner::new(context, "test", input.as_bytes());
+    Parser::new("test", scanner, context)
+}
+
+/// parses and evaluates each line of `input` in `context`, returning the
+/// values or the error of every line in turn
+#[cfg(test)]
+pub(crate) fn eval_lines<'a>(
+    context: &'a RwLock<Context<'a>>,
+    input: &'a str,
+) -> Vec<Result<Vec<Value>, Error>> {
+    let mut parser = test_parser(context, input);
+    let mut results = Vec::new();
+    loop {
+        let exprs = match parser.line() {
+            Ok(Some(exprs)) => exprs,
+            Ok(Option::None) => return results,
+            Err(e) => {
+                results.push(Err(e));
+                continue;
+            }
+        };
+        results.push(context.write().unwrap().eval(&exprs));
+    }
+}
+
+#[test]
+fn function_defn() {
+    use crate::config::Config;
+    let conf = Config::default();
+    let context = RwLock::new(Context::new(&conf));
+    let input = "op x plus y = z = x + y; z\n\
+                 op plus x =\n\
+                 x * 2\n\
+                 \n\
+                 3 plus 4\n\
+                 plus 5\n\
+                 op plus x = )\n\
+                 plus 5\n\
+                 z\n";
+    let mut results = eval_lines(&context, input).into_iter();
+    let mut eval = || results.next().unwrap();
+    assert_eq!(eval(), Ok(vec![]));
+    assert_eq!(eval(), Ok(vec![]));
+    assert_eq!(eval(), Ok(vec![Value::Int(7)]));
+    assert_eq!(eval(), Ok(vec![Value::Int(10)]));
+    // a failed definition leaves the previous one in place
+    assert!(eval().is_err());
+    assert_eq!(eval(), Ok(vec![Value::Int(10)]));
+    // z was local to plus
+    assert!(eval().is_err());
+}
+
+#[test]
+fn conditionals() {
+    use crate::config::Config;
+    let conf = Config::default();
+    let context = RwLock::new(Context::new(&conf));
+    let input = "op a gcd b = b: b gcd a mod b; a\n\
+                 12 gcd 18\n\
+                 op count n = n: count n - 1; 7\n\
+                 count 50000\n\
+                 1: 2\n\
+                 )maxstack 10\n\
+                 op f x = f x\n\
+                 f 1\n\
+                 count 5\n";
+    let mut results = eval_lines(&context, input).into_iter();
+    let mut eval = || results.next().unwrap();
+    assert_eq!(eval(), Ok(vec![]));
+    assert_eq!(eval(), Ok(vec![Value::Int(6)]));
+    assert_eq!(eval(), Ok(vec![]));
+    // tail calls run in constant stack
+    assert_eq!(eval(), Ok(vec![Value::Int(7)]));
+    assert!(eval().is_err());
+    // but they count toward the limit, so endless recursion stops
+    assert_eq!(eval(), Ok(vec![]));
+    assert_eq!(eval(), Ok(vec![]));
+    let err = eval().unwrap_err();
+    assert!(err.msg().contains("more than 10 nested calls"), "{err}");
+    assert_eq!(eval(), Ok(vec![Value::Int(7)]));
+}
+
+#[test]
+fn special_commands() {
+    use crate::config::Config;
+    let conf = Config::default();
+    let context = RwLock::new(Context::new(&conf));
+    let input = ")origin 0\n\
+                 iota 3\n\
+                 )seed 7\n\
+                 ?1e9\n\
+                 )seed 7\n\
+                 ?1e9\n\
+                 )ibase 2\n\
+                 )ibase 8\n\
+                 17\n\
+                 )base 37\n";
+    let mut results = eval_lines(&context, input).into_iter();
+    let mut eval = || results.next().unwrap();
+    assert_eq!(eval(), Ok(vec![]));
+    let ints = (0..3).map(Value::Int).collect();
+    assert_eq!(eval(), Ok(vec![Value::Vector(ints)]));
+    assert_eq!(eval(), Ok(vec![]));
+    let roll = eval();
+    assert_eq!(eval(), Ok(vec![]));
+    // the same seed gives the same numbers
+    assert_eq!(eval(), roll);
+    // the arguments are decimal whatever the input base
+    assert_eq!(eval(), Ok(vec![]));
+    assert_eq!(eval(), Ok(vec![]));
+    assert_eq!(eval(), Ok(vec![Value::Int(15)]));
+    assert!(eval().is_err());
+    assert_eq!(context.read().unwrap().config().origin(), 0);
+}
+
+#[test]
+fn input_bases() {
+    use crate::{config::Config, value::eval::ints};
+    let conf = Config::default();
+    let context = RwLock::new(Context::new(&conf));
+    let input = "0b101 0x1f\n\
+                 0b102\n\
+                 0x1g\n\
+                 )ibase 16\n\
+                 1f ff 10\n\
+                 )ibase 2\n\
+                 101\n\
+                 12\n";
+    let results = eval_lines(&context, input);
+    assert_eq!(results[0], Ok(vec![ints(&[5, 31])]));
+    // a digit beyond the base of the literal is an error
+    for bad in &results[1..3] {
+        assert!(bad.as_ref().unwrap_err().msg().contains("bad number"));
+    }
+    assert_eq!(results[4], Ok(vec![ints(&[31, 255, 16])]));
+    assert_eq!(results[6], Ok(vec![Value::Int(5)]));
+    assert!(results[7].is_err());
+}
@@ -0,0 +1,112 @@
+use std::{
+    fmt::Debug,
+    fs::File,
+    io::{self, Read, Write},
+};
+
+use crate::{
+    error::Error, exec::context::Context, scan::Scanner, value::quote,
+};
+
+use super::Parser;
+
+/// writes ivy source that recreates the session in `context`: the settings,
+/// then the user-defined ops in the order they were defined, then the global
+/// variables. The source is read in base 10, so the input base is restored
+/// only at the end.
+fn save(context: &Context, w: &mut impl Write) -> io::Result<()> {
+    let conf = context.config();
+    writeln!(w, "# Saved session; load it with )get.")?;
+    writeln!(w, ")prec {}", conf.float_prec())?;
+    writeln!(w, ")maxbits {}", conf.max_bits())?;
+    writeln!(w, ")maxdigits {}", conf.max_digits())?;
+    writeln!(w, ")maxstack {}", conf.max_stack())?;
+    writeln!(w, ")origin {}", conf.origin())?;
+    writeln!(w, ")seed {}", conf.seed())?;
+    if let Some(prompt) = quote(conf.prompt()) {
+        writeln!(w, ")prompt {prompt}")?;
+    }
+    if let Some(format) = quote(conf.format()) {
+        writeln!(w, ")format {format}")?;
+    }
+    writeln!(w, ")obase {}", conf.output_base())?;
+    writeln!(w, ")ibase 10")?;
+    for fun in context.ops() {
+        writeln!(w, "{fun}")?;
+    }
+    for (name, value) in context.globals() {
+        // the constants are set afresh, and _ belongs to this session
+        if matches!(name, "_" | "pi" | "e") {
+            continue;
+        }
+        writeln!(w, "{name} = {}", value.prog_string())?;
+    }
+    writeln!(w, ")ibase {}", conf.input_base())
+}
+
+impl<'a, R: Read + Debug> Parser<'a, R> {
+    /// `)save file`: writes the session to `file` as ivy source
+    pub(super) fn save(&mut self, file: &str) -> Result<(), Error> {
+        let res = File::create(file).and_then(|mut f| {
+            let context = self.context.read().unwrap();
+            save(&context, &mut f)
+        });
+        if let Err(e) = res {
+            errorf!(self, "save: {file}: {e}");
+        }
+        Ok(())
+    }
+
+    /// `)get file`: runs the ivy source in `file` in the current context,
+    /// printing its values and stopping at the first error
+    pub(super) fn get(&mut self, file: &str) -> Result<(), Error> {
+        let f = match File::open(file) {
+            Ok(f) => f,
+            Err(e) => {
+                errorf!(self, "get: {file}: {e}");
+            }
+        };
+        let scanner = Scanner::new(self.context, file, f);
+        let mut parser = Parser::new(file, scanner, self.context);
+        if !parser.run_all(self.context, false, true) {
+            errorf!(self, "get: {file} failed");
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn round_trip() {
+    use super::eval_lines;
+    use crate::config::Config;
+    use std::sync::RwLock;
+    let conf = Config::default();
+    fn run<'a>(context: &'a RwLock<Context<'a>>, input: &'a str) {
+        assert!(eval_lines(context, input).iter().all(Result::is_ok));
+    }
+    let context = RwLock::new(Context::new(&conf));
+    let input = "op a plus b = a + b\n\
+                 op twice x =\n\
+                 y = x plus x\n\
+                 y\n\
+                 \n\
+                 v = 1 (2 'ab') (-3) (1/3) (sqrt 2)\n\
+                 )origin 0\n\
+                 )seed 7\n";
+    run(&context, input);
+    let mut saved = Vec::new();
+    save(&context.read().unwrap(), &mut saved).unwrap();
+
+    let restored = RwLock::new(Context::new(&conf));
+    let saved = String::from_utf8(saved).unwrap();
+    run(&restored, &saved);
+    let restored = restored.read().unwrap();
+    assert_eq!(restored.config().origin(), 0);
+    assert_eq!(restored.config().seed(), 7);
+    assert_eq!(restored.global("v"), context.read().unwrap().global("v"));
+    let ops: Vec<_> = restored.ops().map(ToString::to_string).collect();
+    assert_eq!(
+        ops,
+        ["op a plus b = a + b", "op twice x =\n\ty = x plus x\n\ty\n"]
+    );
+}
@@ -5,39 +5,74 @@ use std::{
 };
 
 use crate::{
-    config::Config, exec::context::Context, parse::Parser, value::Value,
+    config::Config,
+    error::Error,
+    exec::context::Context,
+    parse::Parser,
+    scan::Scanner,
+    value::{context::expr::Expr, format, Value},
 };
 
-#[derive(Debug)]
-pub struct RunError;
+/// the outcome of a call to [`Parser::run`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RunStatus {
+    /// a line was run, or was empty, and there may be more input
+    Continued,
+    /// the input is exhausted
+    Eof,
+    /// a line failed and the error has been reported
+    Failed,
+}
 
-/// runs the parser/evaluator until EOF or error. The return value says whether
-/// we completed without error. If the return value is true, it means we ran out
-/// of data (EOF) and the run was successful. Typical execution is therefore to
-/// loop calling Run until it succeeds. Error details are reported to the
-/// configured error output stream.
 impl<'a, R: Read + Debug> Parser<'a, R> {
+    /// runs the parser/evaluator on a single line of input. Error details are
+    /// reported to standard error as `file:line: message`, and the caller may
+    /// keep going. Statements that ran before the failing one on the same line
+    /// keep their effects: after `x = 5; 1/0`, `x` is 5. The prompt is printed
+    /// only if `interactive` is set.
     pub fn run(
         &mut self,
-        conf: &Config,
         context: &'a RwLock<Context<'a>>,
         interactive: bool,
-    ) -> Result<(), RunError> {
+    ) -> RunStatus {
         if interactive {
-            print!("{}", conf.prompt());
+            print!("{}", context.read().unwrap().config().prompt());
             // have to flush to get the prompt to go before any input
             std::io::stdout().flush().unwrap();
         }
-        let Ok(exprs) = self.line() else {
-        return Ok(());
-    };
+        let exprs = match self.line() {
+            Ok(Some(exprs)) => exprs,
+            Ok(None) => {
+                if interactive {
+                    // end the line the last prompt is on
+                    println!();
+                }
+                return RunStatus::Eof;
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                return RunStatus::Failed;
+            }
+        };
         let values = if !exprs.is_empty() {
             // TODO match interactive and time it if true
-            context.read().unwrap().eval(exprs)
+            let res = context.write().unwrap().eval(&exprs);
+            match res {
+                Ok(values) => values,
+                Err(e) => {
+                    let (file, line) = self.location();
+                    eprintln!("{}", e.at(file, line));
+                    return RunStatus::Failed;
+                }
+            }
         } else {
             Vec::new()
         };
-        if print_values(conf, &values) {
+        let printed = {
+            let context = context.read().unwrap();
+            print_values(context.config(), &exprs, &values)
+        };
+        if printed {
             // safe to unwrap because print_values checks that we have at least
             // one
             context
@@ -45,23 +80,72 @@ impl<'a, R: Read + Debug> Parser<'a, R> {
                 .unwrap()
                 .assign_global("_", values.last().unwrap().clone());
         }
-        if interactive {
-            println!();
+        RunStatus::Continued
+    }
+
+    /// runs lines until EOF, or until the first failure if `stop_on_error` is
+    /// set. Returns whether every line that was run succeeded.
+    pub fn run_all(
+        &mut self,
+        context: &'a RwLock<Context<'a>>,
+        interactive: bool,
+        stop_on_error: bool,
+    ) -> bool {
+        let mut ok = true;
+        loop {
+            match self.run(context, interactive) {
+                RunStatus::Continued => {}
+                RunStatus::Eof => return ok,
+                RunStatus::Failed => {
+                    ok = false;
+                    if stop_on_error {
+                        return ok;
+                    }
+                }
+            }
         }
-        Err(RunError)
     }
 }
 
-/// neatly prints the values returned from execution, followed by a newline
-fn print_values(_conf: &Config, values: &Vec<Value>) -> bool {
+/// evaluates `text` as a program, as the unary `ivy` does, and returns the
+/// value of the last expression. The text runs in `ctx`, so it may use and
+/// set its variables and define ops.
+pub(crate) fn eval_text(ctx: &mut Context, text: &str) -> Result<Value, Error> {
+    // the scanner and parser need the context behind a lock of their own
+    let context = RwLock::new(ctx.take());
+    let res = eval_lines(&context, text);
+    let state = context.write().unwrap().take();
+    ctx.restore(state);
+    res
+}
+
+fn eval_lines<'a>(
+    context: &'a RwLock<Context<'a>>,
+    text: &str,
+) -> Result<Value, Error> {
+    let scanner = Scanner::new(context, "ivy", text.as_bytes());
+    let mut parser = Parser::new("ivy", scanner, context);
+    let mut last = None;
+    while let Some(exprs) = parser.line()? {
+        let values = context.write().unwrap().eval(&exprs)?;
+        last = values.into_iter().last().or(last);
+    }
+    last.ok_or_else(|| Error::eval("ivy: no value"))
+}
+
+/// neatly prints the values returned from execution, followed by a newline.
+/// The values of assignments are not printed. Returns whether anything was
+/// printed.
+fn print_values(conf: &Config, exprs: &[Expr], values: &[Value]) -> bool {
     if values.is_empty() {
         return false;
     }
     let mut printed = false;
-    for v in values {
-        // TODO filter out printing assignments
-        // TODO handle formatting based on config
-        let s = format!("{}", v);
+    for (e, v) in exprs.iter().zip(values) {
+        if e.is_assignment() {
+            continue;
+        }
+        let s = format::sprint(v, conf);
         if printed && !s.is_empty() && !s.ends_with('\n') {
             print!(" ");
         }
@@ -73,3 +157,46 @@ fn print_values(_conf: &Config, values: &Vec<Value>) -> bool {
     }
     printed
 }
+
+#[test]
+fn run_all_reports_failure() {
+    use crate::parse::test_parser;
+    let conf = Config::default();
+    let context = RwLock::new(Context::new(&conf));
+    let mut parser = test_parser(&context, "1 + 2\n1/0\n3\n");
+    assert_eq!(parser.run(&context, false), RunStatus::Continued);
+    assert_eq!(parser.run(&context, false), RunStatus::Failed);
+    assert_eq!(parser.run(&context, false), RunStatus::Continued);
+    assert_eq!(parser.run(&context, false), RunStatus::Eof);
+
+    let mut parser = test_parser(&context, "1/0\n2 3\n");
+    assert!(!parser.run_all(&context, false, true));
+    // the line after the failure is still there to be read
+    assert_eq!(parser.run(&context, false), RunStatus::Continued);
+}
+
+#[test]
+fn assignments_not_printed() {
+    use crate::parse::test_parser;
+    let conf = Config::default();
+    let context = RwLock::new(Context::new(&conf));
+    let mut parser = test_parser(&context, "x = 5\nx = 6; x\n");
+    let mut printed = || {
+        let exprs = parser.line().unwrap().unwrap();
+        let values = context.write().unwrap().eval(&exprs).unwrap();
+        print_values(context.read().unwrap().config(), &exprs, &values)
+    };
+    assert!(!printed());
+    assert!(printed());
+}
+
+#[test]
+fn demo() {
+    use crate::parse::test_parser;
+    let conf = Config::default();
+    let context = RwLock::new(Context::new(&conf));
+    let mut parser = test_parser(&context, include_str!("../demo.ivy"));
+    // every line runs, to the end of the file
+    assert!(parser.run_all(&context, false, true));
+    assert_eq!(parser.run(&context, false), RunStatus::Eof);
+}
@@ -123,11 +123,15 @@ pub struct Scanner<'a, R: Read> {
     name: String,
     buf: Vec<u8>,
     input: String,
-    last_char: Option<u8>,
+    last_char: Option<char>,
     last_width: usize,
     read_ok: bool,
     line: usize,
 
+    /// an error found while reading input, such as invalid UTF-8, to be
+    /// reported in place of the current token
+    bad_input: Option<String>,
+
     /// current position in the input
     pos: usize,
 
@@ -148,7 +152,8 @@ impl<'a, R: Read + std::fmt::Debug> Scanner<'a, R> {
             last_char: None,
             last_width: 0,
             read_ok: false,
-            line: 0,
+            line: 1,
+            bad_input: None,
             pos: 0,
             start: 0,
             token: Token::default(),
@@ -162,6 +167,11 @@ impl<'a, R: Read + std::fmt::Debug> Scanner<'a, R> {
 
     // TODO looks like reading to newline, which we could probably do with
     // lines()
+    /// reads the next line of input. A line that is not valid UTF-8 is
+    /// dropped and recorded in `bad_input`.
+    // reading a byte at a time keeps the scanner from taking input past the
+    // line it needs, which matters when the reader is shared or interactive
+    #[allow(clippy::unbuffered_bytes)]
     fn load_line(&mut self) {
         self.buf.clear();
         for c in (&mut self.r).bytes() {
@@ -176,48 +186,63 @@ impl<'a, R: Read + std::fmt::Debug> Scanner<'a, R> {
                 break;
             }
         }
+        let line = match std::str::from_utf8(&self.buf) {
+            Ok(line) => line,
+            Err(e) => {
+                let bad = self.buf[e.valid_up_to()];
+                self.bad_input =
+                    Some(format!("invalid UTF-8 encoding (byte {bad:#04x})"));
+                ""
+            }
+        };
         if self.start == self.pos {
-            self.input = String::from_utf8(self.buf.clone()).unwrap();
+            self.input = line.to_owned();
             self.start = 0;
             self.pos = 0;
         } else {
-            self.input
-                .push_str(&String::from_utf8(self.buf.clone()).unwrap())
+            self.input.push_str(line)
         }
     }
 
-    /// None indicates EOF
-    fn read_rune(&mut self) -> (Option<u8>, usize) {
+    /// returns the next character and its width in bytes. None indicates EOF,
+    /// or that the line could not be decoded.
+    fn read_rune(&mut self) -> (Option<char>, usize) {
         if !self.done && self.pos == self.input.len() {
             if !self.read_ok {
                 self.errorf("incomplete token".to_owned());
-                return (Some(b'\n'), 1);
+                return (Some('\n'), 1);
             }
             self.load_line();
         }
-        if self.input.len() == self.pos {
+        if self.bad_input.is_some() {
             return (None, 0);
         }
-        (Some(self.input.as_bytes()[self.pos]), 1)
+        match self.input[self.pos..].chars().next() {
+            Some(c) => (Some(c), c.len_utf8()),
+            None => (None, 0),
+        }
     }
 
-    fn next_inner(&mut self) -> Option<u8> {
+    fn next_inner(&mut self) -> Option<char> {
         (self.last_char, self.last_width) = self.read_rune();
         self.pos += self.last_width;
         self.last_char
     }
 
-    fn peek(&mut self) -> Option<u8> {
+    fn peek(&mut self) -> Option<char> {
         let (c, _) = self.read_rune();
         c
     }
 
     /// return the next two runes without consuming anything
-    fn peek2(&mut self) -> (Option<u8>, Option<u8>) {
-        let pos = self.pos;
+    fn peek2(&mut self) -> (Option<char>, Option<char>) {
+        let (pos, last_char, last_width) =
+            (self.pos, self.last_char, self.last_width);
         let c1 = self.next_inner();
         let c2 = self.next_inner();
-        self.pos = pos;
+        // restore the last character too, so backup still steps over it
+        (self.pos, self.last_char, self.last_width) =
+            (pos, last_char, last_width);
         (c1, c2)
     }
 
@@ -245,7 +270,7 @@ impl<'a, R: Read + std::fmt::Debug> Scanner<'a, R> {
 
     fn accept(&mut self, valid: &str) -> bool {
         if let Some(c) = self.next_inner() {
-            if valid.contains(char::from(c)) {
+            if valid.contains(c) {
                 return true;
             }
         }
@@ -256,7 +281,7 @@ impl<'a, R: Read + std::fmt::Debug> Scanner<'a, R> {
     /// consumes a run of runes from the valid set
     fn accept_run(&mut self, valid: &str) {
         while let Some(c) = self.next_inner() {
-            if !valid.contains(char::from(c)) {
+            if !valid.contains(c) {
                 break;
             }
         }
@@ -264,7 +289,11 @@ impl<'a, R: Read + std::fmt::Debug> Scanner<'a, R> {
     }
 
     fn errorf(&mut self, arg: String) -> Lex {
-        self.token = Token::new(Type::Error, self.start, arg);
+        self.token = Token::new(Type::Error, self.line, arg);
+        // the rest of the line is dropped, newline included
+        if self.input[self.pos..].contains('\n') {
+            self.line += 1;
+        }
         self.start = 0;
         self.pos = 0;
         self.input.clear();
@@ -275,68 +304,74 @@ impl<'a, R: Read + std::fmt::Debug> Scanner<'a, R> {
         self.read_ok = true;
         self.last_char = None;
         self.last_width = 0;
-        self.token = Token::new(Type::Eof, self.pos, String::from("EOF"));
+        self.token = Token::new(Type::Eof, self.line, String::from("EOF"));
         let mut state = Lex::Any;
         loop {
             state = state.run(self);
             if state.is_none() {
+                if let Some(msg) = self.bad_input.take() {
+                    self.errorf(msg);
+                    // the bad line was never added to the input, so errorf
+                    // did not count it
+                    self.line += 1;
+                }
                 return &self.token;
             }
         }
     }
 
-    fn is_numeral(&self, r: u8) -> bool {
-        if (b'0'..=b'9').contains(&r) {
-            return true;
+    /// returns the base in which to scan numbers: the input base, except that
+    /// the arguments of special commands are always decimal
+    fn input_base(&self) -> usize {
+        if self.input.trim_start().starts_with(')') {
+            return 10;
         }
-        let base = self.context.read().unwrap().config().input_base();
-        if base < 10 {
-            return false;
-        }
-        let top = base - 10;
-        if b'a' <= r && r <= b'a' + top as u8 {
-            return true;
-        }
-        if b'A' <= r && r <= b'A' + top as u8 {
-            return true;
-        }
-        false
+        self.context.read().unwrap().config().input_base()
     }
 
-    fn is_operator(&mut self, r: u8) -> bool {
+    /// reports whether `r` is a digit in the input base
+    fn is_numeral(&self, r: char) -> bool {
+        let base = match self.input_base() {
+            0 => 10,
+            base => base as u32,
+        };
+        r.is_digit(base)
+    }
+
+    fn is_operator(&mut self, r: char) -> bool {
         match r {
-            b'?' | b'+' | b'-' | b'/' | b'%' | b'&' | b'|' | b'^' | b',' => {}
-            b'!' => {
+            '?' | '+' | '-' | '/' | '%' | '&' | '|' | '^' | ',' => {}
+            '!' => {
                 if let Some(p) = self.peek() {
-                    if p == b'=' {
+                    if p == '=' {
                         self.next_inner();
                     }
                 }
             }
-            b'>' => {
+            '>' => {
                 if let Some(p) = self.peek() {
-                    if p == b'>' || p == b'=' {
+                    if p == '>' || p == '=' {
                         self.next_inner();
                     }
                 }
             }
-            b'<' => {
+            '<' => {
                 if let Some(p) = self.peek() {
-                    if [b'<', b'='].contains(&p) {
+                    if ['<', '='].contains(&p) {
                         self.next_inner();
                     }
                 }
             }
-            b'*' => {
+            '*' => {
                 if let Some(p) = self.peek() {
-                    if p == b'*' {
+                    if p == '*' {
                         self.next_inner();
                     }
                 }
             }
-            b'=' => {
+            '=' => {
                 if let Some(p) = self.peek() {
-                    if p != b'=' {
+                    if p != '=' {
                         return false;
                     }
                 }
@@ -351,15 +386,14 @@ impl<'a, R: Read + std::fmt::Debug> Scanner<'a, R> {
         let Some(r) = self.peek() else {
 	    return true;
 	};
-        // TODO supposed to check unicode.is_symbol too
-        if is_space(r) || is_end_of_line(r) || r.is_ascii_punctuation() {
+        if is_space(r) || is_end_of_line(r) || is_punct_or_symbol(r) {
             return true;
         }
         if self.pos < self.input.len() {
             let (r1, r2) = self.peek2();
             if let Some(r1) = r1 {
                 if let Some(r2) = r2 {
-                    if r1 == b'o' && r2 == b'.' {
+                    if r1 == 'o' && r2 == '.' {
                         return true;
                     }
                 }
@@ -378,14 +412,18 @@ impl<'a, R: Read + std::fmt::Debug> Scanner<'a, R> {
         following_slash_ok: bool,
         following_j_ok: bool,
     ) -> bool {
-        let base = self.context.read().unwrap().config().input_base();
+        let base = self.input_base();
         let mut digits = digits_for_base(base);
-        // if base 0 (default), accept octal for 0o or oO or hex for 0x or 0X.
-        if base == 0
-            && self.accept("0")
-            && (self.accept("xX") || self.accept("oO"))
-        {
-            digits = digits_for_base(16);
+        // if base 0 (default), accept hex for 0x or 0X, octal for 0o or 0O
+        // and binary for 0b or 0B.
+        if base == 0 && self.accept("0") {
+            if self.accept("xX") {
+                digits = digits_for_base(16);
+            } else if self.accept("oO") {
+                digits = digits_for_base(8);
+            } else if self.accept("bB") {
+                digits = digits_for_base(2);
+            }
         }
         self.accept_run(&digits);
         if self.accept(".") {
@@ -397,18 +435,18 @@ impl<'a, R: Read + std::fmt::Debug> Scanner<'a, R> {
             self.accept_run("0123456789");
         }
         if let Some(r) = self.peek() {
-            if following_slash_ok && r == b'/' {
+            if following_slash_ok && r == '/' {
                 return true;
             }
-            if following_j_ok && r == b'j' {
+            if following_j_ok && r == 'j' {
                 return true;
             }
-            if r != b'o' && is_alpha_numeric(r) {
-                self.next_token();
+            if r != 'o' && is_alpha_numeric(r) {
+                self.next_inner();
                 return false;
             }
-            if r == b'.' || !self.at_terminator() {
-                self.next_token();
+            if r == '.' || !self.at_terminator() {
+                self.next_inner();
                 return false;
             }
         }
@@ -426,15 +464,25 @@ fn digits_for_base(mut base: usize) -> String {
     const LOWER: &str = "abcdefghijklmnopqrstuvwxyz";
     const UPPER: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
     if base <= 10 {
-        String::from(&DECIMAL[..10])
+        String::from(&DECIMAL[..base])
     } else {
         String::from(DECIMAL) + &LOWER[..base - 10] + &UPPER[..base - 10]
     }
 }
 
 #[allow(unused)]
-fn is_alpha_numeric(r: u8) -> bool {
-    r == b'_' || r.is_ascii_alphabetic() || r.is_ascii_digit()
+fn is_alpha_numeric(r: char) -> bool {
+    r == '_' || r.is_alphabetic() || r.is_ascii_digit()
+}
+
+/// reports whether `r` is punctuation or a symbol. Rust has no Unicode
+/// categories, so any non-ASCII character that is neither alphanumeric nor
+/// space counts as a symbol.
+fn is_punct_or_symbol(r: char) -> bool {
+    if r.is_ascii() {
+        return r.is_ascii_punctuation();
+    }
+    !r.is_alphanumeric() && !r.is_whitespace()
 }
 
 #[allow(unused)]
@@ -455,11 +503,8 @@ impl Lex {
     fn run<R: Read + Debug>(self, l: &mut Scanner<R>) -> Self {
         match self {
             Lex::Comment => {
-                loop {
-                    let Some(r) = l.next_inner() else {
-			break
-		    };
-                    if r == b'\n' {
+                while let Some(r) = l.next_inner() {
+                    if r == '\n' {
                         break;
                     }
                 }
@@ -475,30 +520,26 @@ impl Lex {
 		    return Self::None
 		};
                 match r {
-                    b'\n' => return l.emit(Type::Newline),
-                    b';' => return l.emit(Type::Semicolon),
-                    b'#' => return Self::Comment,
-                    b' ' | b'\t' => return Self::Space,
-                    b'\'' | b'"' => {
+                    '\n' => return l.emit(Type::Newline),
+                    ';' => return l.emit(Type::Semicolon),
+                    '#' => return Self::Comment,
+                    ' ' | '\t' => return Self::Space,
+                    '\'' | '"' => {
                         // backup so lex can read the quote
                         l.backup();
                         return Self::Quote;
                     }
-                    b'`' => return Self::RawQuote,
-                    b'-' | b'+' => {
+                    '`' => return Self::RawQuote,
+                    '-' | '+' => {
                         if l.start > 0 {
-                            let rr =
-                                l.input[..l.start].as_bytes().last().unwrap();
-                            if rr.is_ascii_alphanumeric()
-                                || *rr == b')'
-                                || *rr == b']'
-                            {
+                            let rr = l.input[..l.start].chars().last().unwrap();
+                            if rr.is_alphanumeric() || rr == ')' || rr == ']' {
                                 return Self::Operator;
                             }
                             let (r1, r2) = l.peek2();
                             if let Some(r1) = r1 {
                                 if let Some(r2) = r2 {
-                                    if r1 == b'.' && !l.is_numeral(r2) {
+                                    if r1 == '.' && !l.is_numeral(r2) {
                                         return Self::Operator;
                                     }
                                 }
@@ -509,14 +550,14 @@ impl Lex {
                     }
                     _ => {}
                 };
-                if r == b'.' || (b'0'..=b'9').contains(&r) {
+                if r == '.' || r.is_ascii_digit() {
                     l.backup();
                     return Lex::Complex;
                 }
-                if r == b'=' {
+                if r == '=' {
                     let p = l.peek();
                     if let Some(c) = p {
-                        if c != b'=' {
+                        if c != '=' {
                             return l.emit(Type::Assign);
                         }
                     }
@@ -531,17 +572,20 @@ impl Lex {
                     return Self::Identifier;
                 }
                 match r {
-                    b'[' => return l.emit(Type::LeftBrack),
-                    b':' => return l.emit(Type::Colon),
-                    b']' => return l.emit(Type::RightBrack),
-                    b'(' => return l.emit(Type::LeftParen),
-                    b')' => return l.emit(Type::RightParen),
+                    '[' => return l.emit(Type::LeftBrack),
+                    ':' => return l.emit(Type::Colon),
+                    ']' => return l.emit(Type::RightBrack),
+                    '(' => return l.emit(Type::LeftParen),
+                    ')' => return l.emit(Type::RightParen),
                     _ => {}
                 }
                 if r.is_ascii() {
                     return l.emit(Type::Char);
                 }
-                l.errorf(format!("unrecognized character {:?}", r))
+                l.errorf(format!(
+                    "unrecognized character {r:?} (U+{:04X})",
+                    u32::from(r)
+                ))
             }
             Lex::Space => {
                 while let Some(c) = l.peek() {
@@ -561,7 +605,9 @@ impl Lex {
                     l.next_inner();
                 }
                 if !l.at_terminator() {
-                    let e = format!("bad character {:?}", l.next_inner());
+                    let r = l.next_inner().unwrap_or_default();
+                    let e =
+                        format!("bad character {r:?} (U+{:04X})", u32::from(r));
                     return l.errorf(e);
                 }
                 let word = l.word();
@@ -569,16 +615,13 @@ impl Lex {
                     return l.emit(Type::Op);
                 } else if word == "o" {
                     if let Some(c) = l.peek() {
-                        if c == b'.' {
+                        if c == '.' {
                             return Self::Operator;
                         }
                     }
                 } else if l.defined(word) {
                     return Self::Operator;
-                } else if is_all_digits(
-                    word,
-                    l.context.read().unwrap().config().input_base(),
-                ) {
+                } else if is_all_digits(word, l.input_base()) {
                     l.pos = l.start;
                     return Self::Complex;
                 }
@@ -593,26 +636,26 @@ impl Lex {
                     if let Some(p) = l.peek() {
                         match p {
                             // reduction or scan
-                            b'/' | b'\\' => {
+                            '/' | '\\' => {
                                 l.next_inner();
                             }
-                            b'.' => {
+                            '.' => {
                                 // inner or outer product?
                                 l.next_inner();
-                                if let Some(pp) = l.peek() && is_digit(pp) {
-				    l.backup();
-				    return l.emit(Type::Operator)
-				}
+                                if l.peek().is_some_and(is_digit) {
+                                    l.backup();
+                                    return l.emit(Type::Operator);
+                                }
                                 let prev_pos = l.pos;
                                 if let Some(r) = l.next_inner() {
                                     l.is_operator(r);
                                     if is_alpha_numeric(r) {
                                         let r = loop {
-                                            if let Some(r) = l.next_inner() && is_alpha_numeric(r) {
-						continue;
-					    } else {
-						break r
-					    }
+                                            let next = l.next_inner();
+                                            if !next.is_some_and(is_alpha_numeric)
+                                            {
+                                                break r;
+                                            }
                                         };
                                         l.backup();
                                         if !l.at_terminator() {
@@ -662,13 +705,12 @@ impl Lex {
                     let Some(r) = l.next_inner() else {
 			return l.errorf("unterminated quoted string".to_owned());
 		    };
-                    if r == b'\\' {
-                        if let Some(r) = l.next_inner() && r != b'\n' {
-			    // pass
-			} else {
-			    return l.errorf("unterminated quoted string".to_owned());
-			}
-                    } else if r == b'\n' {
+                    if r == '\\' {
+                        if l.next_inner().is_none_or(|r| r == '\n') {
+                            return l
+                                .errorf("unterminated quoted string".to_owned());
+                        }
+                    } else if r == '\n' {
                         return l
                             .errorf("unterminated quote string".to_owned());
                     } else if r == quote {
@@ -681,7 +723,7 @@ impl Lex {
                     // here we can accept a newline mid-token.
                     l.read_ok = true;
                     if let Some(r) = l.next_inner() {
-                        if r == b'`' {
+                        if r == '`' {
                             return l.emit(Type::String);
                         }
                     } else {
@@ -719,11 +761,11 @@ fn accept_number(
     // optional leading sign
     if l.accept("+-") && real_part {
         if let Some(r) = l.peek() {
-            if r == b'/' || r == b'\\' {
-                l.next_token();
+            if r == '/' || r == '\\' {
+                l.next_inner();
                 return (false, l.emit(Type::Operator));
             }
-            if r != b'.' && !l.is_numeral(r) {
+            if r != '.' && !l.is_numeral(r) {
                 return (false, Lex::Operator);
             }
         }
@@ -732,23 +774,23 @@ fn accept_number(
         return (false, l.errorf(format!("bad number syntax: {}", l.word())));
     }
     if let Some(r) = l.peek() {
-        if r != b'/' {
+        if r != '/' {
             return (true, Lex::Any);
         }
     }
     l.accept("/");
 
-    if real_part && let Some(r) = l.peek() && r != b'.' && !l.is_numeral(r) {
-    	    // oops, not a rational. back up!
-    	    l.pos -= 1;
-    	    return (true, Lex::Operator);
-    	}
+    if real_part && l.peek().is_some_and(|r| r != '.' && !l.is_numeral(r)) {
+        // oops, not a rational. back up!
+        l.pos -= 1;
+        return (true, Lex::Operator);
+    }
 
     if !l.scan_number(false, real_part) {
         return (false, l.errorf(format!("bad number syntax: {}", l.word())));
     }
-    if let Some(p) = l.peek() && p == b'.' {
-	return (false, l.errorf(format!("bad number syntax: {}", l.word())));
+    if l.peek() == Some('.') {
+        return (false, l.errorf(format!("bad number syntax: {}", l.word())));
     }
     (true, Lex::Any)
 }
@@ -775,39 +817,54 @@ fn is_identifier(s: &str) -> bool {
     true
 }
 
-/// reports whether b is an ASCII digit
-fn is_digit(b: u8) -> bool {
-    (b'0'..=b'9').contains(&b)
+/// reports whether r is an ASCII digit
+fn is_digit(r: char) -> bool {
+    r.is_ascii_digit()
 }
 
-fn is_end_of_line(r: u8) -> bool {
-    r == b'\n' || r == b';'
+fn is_end_of_line(r: char) -> bool {
+    r == '\n' || r == ';'
 }
 
-fn is_space(r: u8) -> bool {
-    r == b' ' || r == b'\t'
+fn is_space(r: char) -> bool {
+    r == ' ' || r == '\t'
 }
 
+/// reports whether `s` is made of digits in `base`, with at most one `j`
+/// among them, so that it is a number rather than an identifier
 fn is_all_digits(s: &str, base: usize) -> bool {
-    let base = base as u8;
-    let top = b'a' + base - 10 - 1;
-    let ctop = b'A' + base - 10 - 1;
+    let base = if base == 0 { 10 } else { base as u32 };
     let mut sawj = false;
-    for c in s.bytes() {
-        if c == b'j' && !sawj {
+    for c in s.chars() {
+        if c == 'j' && !sawj {
             sawj = true;
             continue;
         }
-        if (b'0'..=b'9').contains(&c) {
-            continue;
-        }
-        if (b'a'..=top).contains(&c) {
-            continue;
-        }
-        if (b'A'..=ctop).contains(&c) {
-            continue;
+        if c.to_digit(base).is_none() {
+            return false;
         }
-        return false;
     }
     true
 }
+
+#[test]
+fn unicode() {
+    use crate::config::Config;
+    let conf = Config::default();
+    let context = RwLock::new(Context::new(&conf));
+    let input: &[u8] = b"code '\xf0\x9f\x92\xa9'+\xc3\xa9\n\xff\n1\n";
+    let mut l = Scanner::new(&context, "test", input);
+    let mut next = || {
+        let tok = l.next_token();
+        (tok.typ, tok.text.clone(), tok.line)
+    };
+    assert_eq!(next(), (Type::Identifier, "code".to_owned(), 1));
+    assert_eq!(next(), (Type::String, "'💩'".to_owned(), 1));
+    assert_eq!(next(), (Type::Operator, "+".to_owned(), 1));
+    assert_eq!(next(), (Type::Identifier, "é".to_owned(), 1));
+    assert_eq!(next().0, Type::Newline);
+    let (typ, text, line) = next();
+    assert_eq!((typ, line), (Type::Error, 2));
+    assert!(text.starts_with("invalid UTF-8"), "{text}");
+    assert_eq!(next(), (Type::Number, "1".to_owned(), 3));
+}
@@ -1,52 +1,160 @@
-use num::{Complex, FromPrimitive, Rational64};
-use std::{error::Error, fmt::Display};
+use num::{BigInt, BigRational, Complex, Num, One, ToPrimitive, Zero};
+use std::{cmp::Ordering, fmt::Display};
 
-use crate::{config::Config, parse::ParseError};
+use crate::{
+    config::Config,
+    error::{errorf, Error},
+};
+
+use self::{bigfloat::BigFloat, matrix::Matrix};
 
 // might embed this as ValueType in Value struct that also contains is_assigment
 // field. see parse/assign.go
 #[derive(Clone, Debug, Default, PartialEq)]
 pub enum Value {
-    Float(f64),
+    Float(BigFloat),
     Int(i64),
+    BigInt(BigInt),
     Complex(Complex<f64>),
-    Rational(Rational64),
+    Rational(BigRational),
     Char(char),
+    Vector(Vec<Value>),
+    Matrix(Matrix),
     #[default]
     None,
 }
 
+/// converts a `BigInt` into a `Value`, shrinking it back to an `Int` if it fits
+impl From<BigInt> for Value {
+    fn from(b: BigInt) -> Self {
+        match b.to_i64() {
+            Some(i) => Self::Int(i),
+            None => Self::BigInt(b),
+        }
+    }
+}
+
+/// converts a `BigRational` into a `Value`, normalizing it to an integer if the
+/// denominator is 1
+impl From<BigRational> for Value {
+    fn from(r: BigRational) -> Self {
+        if r.denom().is_one() {
+            return r.to_integer().into();
+        }
+        Self::Rational(r)
+    }
+}
+
+/// converts a `BigFloat` into a `Value`, shrinking it to an integer if it is an
+/// integer that can be represented exactly at its precision
+impl From<BigFloat> for Value {
+    fn from(f: BigFloat) -> Self {
+        if f.is_int() && f.int_bits() <= f.prec() as i64 {
+            return f.to_big_int().into();
+        }
+        Self::Float(f)
+    }
+}
+
 impl Value {
-    fn complex(v1: Value, v2: Value) -> Self {
-        if let Self::Int(v1) = v1 {
-            if let Self::Int(v2) = v2 {
-                return Self::Complex(Complex {
-                    re: v1 as f64,
-                    im: v2 as f64,
-                });
-            }
+    pub(crate) fn complex(v1: Value, v2: Value) -> Result<Self, Error> {
+        let (Some(re), Some(im)) = (v1.to_f64(), v2.to_f64()) else {
+            errorf!("cannot make a complex number from {v1} and {v2}");
+        };
+        let c = Complex { re, im };
+        if !c.is_finite() {
+            errorf!("complex number out of range");
         }
-        todo!()
+        Ok(Self::Complex(c))
     }
-    fn rational(v1: Value, v2: Value) -> Self {
-        if let Self::Int(v1) = v1 {
-            if let Self::Int(v2) = v2 {
-                return Self::Rational(Rational64::new(v1, v2));
-            }
+
+    /// converts a complex result into a `Value`, demoting it to a real number
+    /// with precision `prec` if its imaginary part is zero. It is an error if
+    /// either part is infinite or NaN.
+    pub(crate) fn from_complex(
+        c: Complex<f64>,
+        prec: usize,
+    ) -> Result<Self, Error> {
+        if !c.is_finite() {
+            errorf!("complex result out of range");
         }
+        if c.im == 0.0 {
+            return Ok(BigFloat::from_f64(c.re, prec)?.into());
+        }
+        Ok(Self::Complex(c))
+    }
 
-        if let Self::Rational(v1) = v1 {
-            if let Self::Int(v2) = v2 {
-                return Self::Rational(v1 / Rational64::from(v2));
-            }
+    /// returns the value as an f64 if it is real
+    pub(crate) fn to_f64(&self) -> Option<f64> {
+        match self {
+            Self::Int(i) => Some(*i as f64),
+            Self::BigInt(b) => b.to_f64(),
+            Self::Rational(r) => r.to_f64(),
+            Self::Float(f) => Some(f.to_f64()),
+            _ => None,
         }
+    }
 
-        if let Self::Int(v1) = v1 {
-            if let Self::Rational(v2) = v2 {
-                return Self::Rational(Rational64::from(v1) / v2);
-            }
+    /// returns the value as a `BigFloat` with precision `prec` if it is real
+    pub(crate) fn to_big_float(&self, prec: usize) -> Option<BigFloat> {
+        match self {
+            Self::Float(f) => Some(f.with_prec(prec)),
+            _ => Some(BigFloat::from_big_rat(&self.to_big_rat()?, prec)),
+        }
+    }
+
+    /// returns the value as a `BigInt` if it is an [`Int`] or [`BigInt`]
+    ///
+    /// [`Int`]: Value::Int
+    /// [`BigInt`]: Value::BigInt
+    pub(crate) fn to_big_int(&self) -> Option<BigInt> {
+        match self {
+            Self::Int(i) => Some(BigInt::from(*i)),
+            Self::BigInt(b) => Some(b.clone()),
+            _ => None,
         }
-        panic!("tried to make a rational from {v1:#?} and {v2:#?}");
+    }
+
+    /// returns the value as a `BigRational` if it is an integer or a
+    /// [`Rational`]
+    ///
+    /// [`Rational`]: Value::Rational
+    pub(crate) fn to_big_rat(&self) -> Option<BigRational> {
+        match self {
+            Self::Rational(r) => Some(r.clone()),
+            _ => self.to_big_int().map(BigRational::from_integer),
+        }
+    }
+
+    pub(crate) fn rational(v1: Value, v2: Value) -> Result<Self, Error> {
+        let (Some(n), Some(d)) = (v1.to_big_rat(), v2.to_big_rat()) else {
+            errorf!("cannot make a rational from {v1} and {v2}");
+        };
+        if d.is_zero() {
+            errorf!("division by zero");
+        }
+        Ok((n / d).into())
+    }
+
+    /// compares two scalars: numbers by value and characters by code point.
+    /// Returns `None` if they cannot be ordered, as for complex numbers or a
+    /// character and a number.
+    pub(crate) fn compare(&self, other: &Value) -> Option<Ordering> {
+        if let (Self::Char(a), Self::Char(b)) = (self, other) {
+            return Some(a.cmp(b));
+        }
+        if let (Some(a), Some(b)) = (self.to_big_rat(), other.to_big_rat()) {
+            return Some(a.cmp(&b));
+        }
+        // at least one is a float, so compare at the greater precision
+        let prec = [self, other]
+            .iter()
+            .filter_map(|v| match v {
+                Self::Float(f) => Some(f.prec()),
+                _ => Option::None,
+            })
+            .max()?;
+        Some(self.to_big_float(prec)?.cmp(&other.to_big_float(prec)?))
     }
 
     /// Returns `true` if the value is [`Float`].
@@ -64,132 +172,401 @@ impl Value {
     pub fn is_int(&self) -> bool {
         matches!(self, Self::Int(..))
     }
+
+    /// reports whether the value is a number equal to zero
+    pub fn is_zero(&self) -> bool {
+        match self {
+            Self::Int(i) => *i == 0,
+            Self::BigInt(b) => b.is_zero(),
+            Self::Rational(r) => r.is_zero(),
+            Self::Float(f) => f.is_zero(),
+            Self::Complex(c) => c.is_zero(),
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the value is [`Complex`].
+    ///
+    /// [`Complex`]: Value::Complex
+    #[must_use]
+    pub fn is_complex(&self) -> bool {
+        matches!(self, Self::Complex(..))
+    }
+
+    /// Returns `true` if the value is [`Vector`].
+    ///
+    /// [`Vector`]: Value::Vector
+    #[must_use]
+    pub fn is_vector(&self) -> bool {
+        matches!(self, Self::Vector(..))
+    }
+
+    /// Returns `true` if the value is [`Matrix`].
+    ///
+    /// [`Matrix`]: Value::Matrix
+    #[must_use]
+    pub fn is_matrix(&self) -> bool {
+        matches!(self, Self::Matrix(..))
+    }
+
+    /// returns the elements of the value in row-major order. A scalar is its
+    /// own only element.
+    pub fn into_elems(self) -> Vec<Value> {
+        match self {
+            Self::Vector(v) => v,
+            Self::Matrix(m) => m.into_data(),
+            v => vec![v],
+        }
+    }
+
+    /// returns the number of bits in an integer, or in the larger part of a
+    /// rational, or 0 for any other value
+    pub(crate) fn bits(&self) -> u64 {
+        match self {
+            Self::Int(i) => u64::from(64 - i.unsigned_abs().leading_zeros()),
+            Self::BigInt(b) => b.bits(),
+            Self::Rational(r) => r.numer().bits().max(r.denom().bits()),
+            _ => 0,
+        }
+    }
+
+    /// returns the name of the value's type, as error messages show it
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::Int(_) => "int",
+            Self::BigInt(_) => "big int",
+            Self::Rational(_) => "rational",
+            Self::Float(_) => "float",
+            Self::Complex(_) => "complex",
+            Self::Char(_) => "char",
+            Self::Vector(_) => "vector",
+            Self::Matrix(_) => "matrix",
+            Self::None => "no value",
+        }
+    }
+
+    /// returns the shape of the value: empty for a scalar, the length of a
+    /// vector, or the dimensions of a matrix
+    pub fn shape(&self) -> Vec<usize> {
+        match self {
+            Self::Vector(v) => vec![v.len()],
+            Self::Matrix(m) => m.shape().to_vec(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns `true` if the value is [`BigInt`].
+    ///
+    /// [`BigInt`]: Value::BigInt
+    #[must_use]
+    pub fn is_big_int(&self) -> bool {
+        matches!(self, Self::BigInt(..))
+    }
 }
 
 impl Display for Value {
     fn fmt(&self, w: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Value::Float(f) => write!(w, "{:.8}", *f),
+            Value::Float(f) => write!(w, "{f}"),
             Value::Int(d) => write!(w, "{d}"),
+            Value::BigInt(b) => write!(w, "{b}"),
             Value::Complex(c) => write!(w, "{c}"),
             Value::Rational(r) => write!(w, "{r}"),
-            Value::None => todo!(),
+            // the result of an op that returns nothing prints as nothing
+            Value::None => Ok(()),
             Value::Char(c) => write!(w, "{c}"),
+            Value::Vector(v) => {
+                // vectors of chars print as strings, without spaces
+                if v.iter().all(|e| matches!(e, Value::Char(_))) {
+                    for e in v {
+                        write!(w, "{e}")?;
+                    }
+                    return Ok(());
+                }
+                for (i, e) in v.iter().enumerate() {
+                    if i > 0 {
+                        write!(w, " ")?;
+                    }
+                    if e.is_vector() || e.is_matrix() {
+                        write!(w, "({e})")?;
+                    } else {
+                        write!(w, "{e}")?;
+                    }
+                }
+                Ok(())
+            }
+            Value::Matrix(m) => write!(w, "{m}"),
+        }
+    }
+}
+
+impl Value {
+    /// returns ivy source text that evaluates to the value, as `)save` writes
+    /// it. Floats are written as the exact rationals they hold.
+    pub(crate) fn prog_string(&self) -> String {
+        match self {
+            Value::Int(i) => i.to_string(),
+            Value::BigInt(b) => b.to_string(),
+            Value::Rational(r) => format!("{}/{}", r.numer(), r.denom()),
+            Value::Float(f) => {
+                format!("float {}", Value::from(f.to_big_rat()).prog_string())
+            }
+            // f64s print in a decimal form that reads back exactly
+            Value::Complex(c) => {
+                format!("{} j {}", operand(c.re.to_string()), c.im)
+            }
+            Value::Char(c) => match quote(&c.to_string()) {
+                Some(q) => q,
+                Option::None => format!("char {}", u32::from(*c)),
+            },
+            Value::Vector(v) if v.is_empty() => String::from("iota 0"),
+            Value::Vector(v) if v.len() == 1 => {
+                format!("1 rho {}", operand(v[0].prog_string()))
+            }
+            Value::Vector(v) => {
+                let chars: Option<String> = v
+                    .iter()
+                    .map(|e| match e {
+                        Value::Char(c) => Some(*c),
+                        _ => Option::None,
+                    })
+                    .collect();
+                if let Some(q) = chars.as_deref().and_then(quote) {
+                    return q;
+                }
+                let elems: Vec<_> =
+                    v.iter().map(|e| operand(e.prog_string())).collect();
+                elems.join(" ")
+            }
+            Value::Matrix(m) => {
+                let data = Value::Vector(m.data().to_vec());
+                let shape = matrix::shape_string(m.shape());
+                format!("{shape} rho {}", data.prog_string())
+            }
+            Value::None => String::new(),
         }
     }
 }
 
-pub fn parse_string(text: String) -> String {
-    unquote(text).unwrap_or_else(|_| panic!("invalid string syntax"))
+/// returns ivy source text for the operand `s`, which is wrapped in
+/// parentheses unless it is a single token: a name, a non-negative integer or
+/// a quoted string
+pub(crate) fn operand(s: String) -> String {
+    let is_name = s.chars().all(|c| c.is_alphanumeric() || c == '_');
+    let is_string = s.len() >= 2
+        && s.starts_with(['\'', '"'])
+        && s.ends_with(&s[..1])
+        && !s[1..s.len() - 1].contains(&s[..1]);
+    if !s.is_empty() && (is_name || is_string) {
+        s
+    } else {
+        format!("({s})")
+    }
+}
+
+/// quotes `s` so that [`parse_string`] reads it back, choosing a quote that
+/// does not appear in it. Returns `None` if there is no such quote or `s`
+/// holds a newline.
+pub(crate) fn quote(s: &str) -> Option<String> {
+    if s.contains('\n') {
+        return Option::None;
+    }
+    let q = ['\'', '"', '`'].into_iter().find(|&q| !s.contains(q))?;
+    Some(format!("{q}{s}{q}"))
+}
+
+pub fn parse_string(text: String) -> Result<String, Error> {
+    unquote(text).ok_or_else(|| Error::parse("invalid string syntax"))
 }
 
 /// unquote is a simplified strconv.Unquote that treats ' and " equally. Raw
-/// quotes are Go-like and bounded by ``. The return value is the string and a
-/// boolean rather than error, which was almost always the same anyway.
-fn unquote(s: String) -> Result<String, ParseError> {
+/// quotes are Go-like and bounded by ``. The return value is an `Option` rather
+/// than an error, which was almost always the same anyway.
+fn unquote(s: String) -> Option<String> {
     let n = s.len();
     if n < 2 {
-        return Err(ParseError);
+        return Option::None;
     }
     let chars: Vec<_> = s.chars().collect();
     let quote = chars[0];
-    if quote != chars[n - 1] {
-        return Err(ParseError);
+    if quote != chars[chars.len() - 1] {
+        return Option::None;
     }
     // NOTE Go version looks at bytes not chars, might need to use u8
-    let chars = &chars[1..n - 1];
+    let chars = &chars[1..chars.len() - 1];
     if quote == '`' {
         if chars.contains(&'`') {
-            return Err(ParseError);
+            return Option::None;
         }
-        return Ok(chars.iter().collect());
+        return Some(chars.iter().collect());
     }
 
     if quote != '"' && quote != '\'' {
-        return Err(ParseError);
+        return Option::None;
     }
 
     if s.contains('\n') {
-        return Err(ParseError);
+        return Option::None;
     }
 
     if !s.contains('\\') && !s.contains(quote) {
-        return Ok(chars.iter().collect());
+        return Some(chars.iter().collect());
     }
 
     // TODO utf8 stuff
-    Ok(chars.iter().collect())
+    Some(chars.iter().collect())
 }
 
-pub fn parse(conf: &Config, s: &str) -> Result<Value, ParseError> {
+pub fn parse(conf: &Config, s: &str) -> Result<Value, Error> {
     let (v1, v2, sep) = parse_two(conf, s)?;
     match sep {
         // a complex
-        "j" => return Ok(Value::complex(v1, v2)),
+        "j" => return Value::complex(v1, v2),
         // a rational. NOTE: skipping "tricky" case of big nums
-        "/" => return Ok(Value::rational(v1, v2)),
+        "/" => return Value::rational(v1, v2),
         _ => {}
     }
     // not a rational, but might be something like 1.3e-2, which could become a
     // rational.
     if let Ok(i) = set_int_string(conf, s) {
-        return Ok(Value::Int(i));
+        conf.check_bits(i.bits())?;
+        return Ok(i);
     }
-    if let Ok(r) = set_big_rat_from_float_string(s) {
+    if let Some(r) = set_big_rat_from_float_string(conf, s)? {
         return Ok(r);
     }
-    if let Ok(r) = s.parse::<f64>() {
-        return Ok(Value::Float(r));
+    match s.parse::<f64>() {
+        Ok(r) if r.is_finite() => {
+            Ok(Value::Float(BigFloat::from_f64(r, conf.float_prec())?))
+        }
+        _ => Err(Error::parse(format!("bad number syntax: {s}"))),
     }
-    Err(ParseError)
 }
 
-fn set_big_rat_from_float_string(s: &str) -> Result<Value, Box<dyn Error>> {
+/// parses a decimal string like `1.2` or `1.3e-2` exactly into a rational,
+/// shrinking it to an integer if possible. A number with more bits than
+/// [`Config::max_bits`] allows, like `1e1000000`, is read as a float instead,
+/// which is an error if it is too large even for that. Returns `None` if the
+/// string is not a decimal number.
+fn set_big_rat_from_float_string(
+    conf: &Config,
+    s: &str,
+) -> Result<Option<Value>, Error> {
     if !s.contains(['.', 'e', 'E']) {
-        panic!("bad number syntax: {s}");
+        return Ok(Option::None);
+    }
+    let (mant, exp) = match s.split_once(['e', 'E']) {
+        Some((mant, exp)) => match exp.parse::<i64>() {
+            Ok(exp) => (mant, exp),
+            Err(_) => return Ok(Option::None),
+        },
+        Option::None => (s, 0),
+    };
+    let (neg, mant) = match mant.strip_prefix('-') {
+        Some(mant) => (true, mant),
+        None => (false, mant.strip_prefix('+').unwrap_or(mant)),
+    };
+    let (int, frac) = mant.split_once('.').unwrap_or((mant, ""));
+    let digits = String::from(int) + frac;
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Ok(Option::None);
+    }
+    let Ok(mut n) = digits.parse::<BigInt>() else {
+        return Ok(Option::None);
+    };
+    if neg {
+        n = -n;
+    }
+    let exp = exp.saturating_sub(frac.len() as i64);
+    let Ok(scale) = usize::try_from(exp.unsigned_abs()) else {
+        return Ok(Option::None);
+    };
+    // check the size before computing the power of ten, which has about
+    // log2(10) bits per digit
+    let scale_bits = (scale as f64 * std::f64::consts::LOG2_10) as u64;
+    let bits = if exp < 0 {
+        n.bits().max(scale_bits)
+    } else {
+        n.bits() + scale_bits
+    };
+    if conf.check_bits(bits).is_err() {
+        let prec = conf.float_prec();
+        let ten = BigFloat::from_big_int(BigInt::from(10), prec);
+        let n = BigFloat::from_big_int(n, prec);
+        return Ok(Some((&n * &ten.powi(exp)?)?.into()));
+    }
+    let scale = num::pow(BigInt::from(10), scale);
+    if exp < 0 {
+        Ok(Some(BigRational::new(n, scale).into()))
+    } else {
+        Ok(Some((n * scale).into()))
     }
-    let f = s.parse::<f64>()?;
-    let r = Rational64::from_f64(f).ok_or(ParseError)?;
-    Ok(Value::rational(
-        Value::Int(*r.numer()),
-        Value::Int(*r.denom()),
-    ))
 }
 
 #[test]
 fn big_rat() {
+    let conf = Config::default();
+    let rat = |s| set_big_rat_from_float_string(&conf, s).unwrap().unwrap();
     assert_eq!(
-        Value::rational(Value::Int(3), Value::Int(2500)),
-        set_big_rat_from_float_string("1.2e-3").unwrap()
+        Value::rational(Value::Int(3), Value::Int(2500)).unwrap(),
+        rat("1.2e-3")
     );
+    assert_eq!(
+        Value::rational(Value::Int(-6), Value::Int(5)).unwrap(),
+        rat("-1.2")
+    );
+    assert_eq!(Value::from(num::pow(BigInt::from(10), 100)), rat("1e100"));
+    // too many bits for maxbits, found before computing them, so a float
+    let float = parse(&conf, "1e1000000").unwrap();
+    assert_eq!(float.to_string(), "1e+1000000");
+    assert!(parse(&conf, "1e999999999").is_err());
+    assert_eq!(parse(&conf, "1e-999999999"), Ok(Value::Int(0)));
+    let huge = String::from("0x") + &"f".repeat(250_001);
+    assert!(parse(&conf, &huge).is_err());
 }
 
+/// parses `s` as an integer in the configured input base, returning an `Int`
+/// if it fits or a `BigInt` otherwise. In the default base, a `0x`, `0o` or
+/// `0b` prefix selects hex, octal or binary.
 fn set_int_string(
     conf: &Config,
     s: &str,
-) -> Result<i64, std::num::ParseIntError> {
-    let mut base = conf.input_base();
-    if base == 0 {
-        base = 10;
-    }
-    if s.len() >= 2 {
-        match &s[..2] {
-            "0x" => {
-                return i64::from_str_radix(s.strip_prefix("0x").unwrap(), 16)
-            }
-            "0o" => {
-                return i64::from_str_radix(s.strip_prefix("0o").unwrap(), 8);
-            }
-            _ => {}
-        }
-    }
-    i64::from_str_radix(s, base as u32)
+) -> Result<Value, num::bigint::ParseBigIntError> {
+    let (neg, digits) = match s.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let (digits, base) = match conf.input_base() {
+        0 => match digits.get(..2) {
+            Some("0x" | "0X") => (&digits[2..], 16),
+            Some("0o" | "0O") => (&digits[2..], 8),
+            Some("0b" | "0B") => (&digits[2..], 2),
+            _ => (digits, 10),
+        },
+        base => (digits, base as u32),
+    };
+    let i = BigInt::from_str_radix(digits, base)?;
+    Ok(if neg { -i } else { i }.into())
+}
+
+#[test]
+fn big_int_string() {
+    let conf = Config::default();
+    assert_eq!(set_int_string(&conf, "0x10").unwrap(), Value::Int(16));
+    assert_eq!(set_int_string(&conf, "-0b101").unwrap(), Value::Int(-5));
+    let mut hex = conf.clone();
+    hex.set_input_base(16);
+    assert_eq!(set_int_string(&hex, "0b1").unwrap(), Value::Int(0xb1));
+    assert_eq!(
+        set_int_string(&conf, "18446744073709551616").unwrap(),
+        Value::BigInt(BigInt::from(1) << 64)
+    );
 }
 
 fn parse_two(
     conf: &Config,
     s: &str,
-) -> Result<(Value, Value, &'static str), ParseError> {
+) -> Result<(Value, Value, &'static str), Error> {
     let (sep, typ) = if s.contains('j') {
         ("j", "complex")
     } else if s.contains('/') {
@@ -199,33 +576,41 @@ fn parse_two(
     };
     let elems: Vec<_> = s.split(sep).collect();
     if elems.len() != 2 || elems[0].is_empty() || elems[1].is_empty() {
-        panic!("bad {typ} number syntax: `{s}`");
+        return Err(Error::parse(format!("bad {typ} number syntax: `{s}`")));
     }
     let v1 = parse(conf, elems[0])?;
     let v2 = parse(conf, elems[1])?;
     Ok((v1, v2, sep))
 }
 
+pub mod bigfloat;
 pub mod eval;
+pub mod format;
+pub mod index;
+pub mod matrix;
 
 pub mod context {
 
-    use crate::exec::context::Context;
+    use crate::{error::Error, exec::context::Context};
 
     use super::Value;
 
     pub mod expr;
 
     pub trait UnaryOp<'a> {
-        fn eval_unary(&self, ctx: &Context<'a>, right: Value) -> Value;
+        fn eval_unary(
+            &self,
+            ctx: &mut Context<'a>,
+            right: Value,
+        ) -> Result<Value, Error>;
     }
 
     pub trait BinaryOp<'a> {
         fn eval_binary(
             &self,
-            ctx: &Context<'a>,
+            ctx: &mut Context<'a>,
             right: Value,
             left: Value,
-        ) -> Value;
+        ) -> Result<Value, Error>;
     }
 }
@@ -0,0 +1,800 @@
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    ops::{Add, Div, Mul, Neg, Sub},
+};
+
+use num::{BigInt, BigRational, Integer, One, Signed, ToPrimitive, Zero};
+
+use crate::error::{errorf, Error};
+
+/// extra bits carried through the transcendental functions so that the result
+/// is correct to the requested precision after rounding
+const GUARD: usize = 32;
+
+/// the largest number of bits in the integer part of a value, as in Go's
+/// math/big. Larger results are errors; results too small to have
+/// `-MAX_EXP` bits are zero.
+const MAX_EXP: i64 = i32::MAX as i64;
+
+/// the largest number of bits in the integer part of the argument of `sin`,
+/// `cos` and `tan`. Reducing the argument modulo 2 pi takes that many more
+/// bits of pi, and the cost grows faster than the size.
+const MAX_TRIG_BITS: i64 = 1 << 14;
+
+/// BigFloat is an arbitrary-precision binary floating-point number with the
+/// value `mant * 2**exp`. `mant` is rounded to at most `prec` bits and has its
+/// trailing zero bits removed, so equal values have equal representations.
+#[derive(Clone, Debug)]
+pub struct BigFloat {
+    mant: BigInt,
+    exp: i64,
+    prec: usize,
+}
+
+/// shifts the magnitude of `x` right by `shift` bits, rounding half away from
+/// zero
+fn round_shr(x: &BigInt, shift: usize) -> BigInt {
+    if shift == 0 {
+        return x.clone();
+    }
+    let half = BigInt::one() << (shift - 1);
+    let mag = (x.abs() + half) >> shift;
+    if x.is_negative() {
+        -mag
+    } else {
+        mag
+    }
+}
+
+/// returns `x` scaled by `2**shift`, rounding when `shift` is negative
+fn shift(x: &BigInt, shift: i64) -> BigInt {
+    if shift >= 0 {
+        x << shift as usize
+    } else {
+        round_shr(x, shift.unsigned_abs() as usize)
+    }
+}
+
+/// returns `atan(1/n)` or `atanh(1/n)`, depending on `alternate`, in fixed
+/// point with `wp` fractional bits
+fn arctan_inv(n: u32, wp: usize, alternate: bool) -> BigInt {
+    let n2 = BigInt::from(n * n);
+    let mut term = (BigInt::one() << wp) / n;
+    let mut sum = term.clone();
+    let mut k = 1u32;
+    loop {
+        term /= &n2;
+        let t = &term / (2 * k + 1);
+        if t.is_zero() {
+            return sum;
+        }
+        if alternate && k % 2 == 1 {
+            sum -= t;
+        } else {
+            sum += t;
+        }
+        k += 1;
+    }
+}
+
+/// returns pi in fixed point with `wp` fractional bits, using Machin's formula
+fn pi_fixed(wp: usize) -> BigInt {
+    let w = wp + GUARD;
+    let pi = 16 * arctan_inv(5, w, true) - 4 * arctan_inv(239, w, true);
+    pi >> GUARD
+}
+
+/// returns ln(2) = 2 atanh(1/3) in fixed point with `wp` fractional bits
+fn ln2_fixed(wp: usize) -> BigInt {
+    (2 * arctan_inv(3, wp + GUARD, false)) >> GUARD
+}
+
+/// returns `exp(x)` for the fixed-point `x` with `wp` fractional bits. `x`
+/// should be small, say less than 1 in magnitude.
+fn exp_fixed(x: &BigInt, wp: usize) -> BigInt {
+    // halve the argument a few times to speed up the series and square the
+    // result back up afterwards. reinterpreting x with HALVINGS more
+    // fractional bits divides it by 2**HALVINGS
+    const HALVINGS: usize = 8;
+    let wp = wp + HALVINGS;
+    let one = BigInt::one() << wp;
+    let mut sum = one.clone();
+    let mut term = one;
+    let mut n = 1u32;
+    loop {
+        term = ((term * x) >> wp) / n;
+        if term.is_zero() {
+            break;
+        }
+        sum += &term;
+        n += 1;
+    }
+    for _ in 0..HALVINGS {
+        sum = (&sum * &sum) >> wp;
+    }
+    sum >> HALVINGS
+}
+
+/// returns `atanh(x)` for the fixed-point `x` with `wp` fractional bits
+fn atanh_fixed(x: &BigInt, wp: usize) -> BigInt {
+    let x2 = (x * x) >> wp;
+    let mut power = x.clone();
+    let mut sum = x.clone();
+    let mut n = 1u32;
+    loop {
+        power = (&power * &x2) >> wp;
+        let t = &power / (2 * n + 1);
+        if t.is_zero() {
+            return sum;
+        }
+        sum += t;
+        n += 1;
+    }
+}
+
+/// returns `atan(x)` for the fixed-point `x` with `wp` fractional bits. `x`
+/// should be small, say less than 1/2 in magnitude.
+fn atan_fixed(x: &BigInt, wp: usize) -> BigInt {
+    let x2 = (x * x) >> wp;
+    let mut power = x.clone();
+    let mut sum = x.clone();
+    let mut n = 1u32;
+    loop {
+        power = -((&power * &x2) >> wp);
+        let t = &power / (2 * n + 1);
+        if t.is_zero() {
+            return sum;
+        }
+        sum += t;
+        n += 1;
+    }
+}
+
+/// returns `(sin(x), cos(x))` for the fixed-point `x` with `wp` fractional
+/// bits. `x` should be in [-pi, pi].
+fn sin_cos_fixed(x: &BigInt, wp: usize) -> (BigInt, BigInt) {
+    let one = BigInt::one() << wp;
+    let mut sin = BigInt::zero();
+    let mut cos = BigInt::zero();
+    let mut term = one;
+    let mut n = 0u32;
+    while !term.is_zero() {
+        match n % 4 {
+            0 => cos += &term,
+            1 => sin += &term,
+            2 => cos -= &term,
+            _ => sin -= &term,
+        }
+        n += 1;
+        term = ((term * x) >> wp) / n;
+    }
+    (sin, cos)
+}
+
+impl BigFloat {
+    /// returns `mant * 2**exp` rounded to `prec` bits
+    pub fn new(mant: BigInt, exp: i64, prec: usize) -> Self {
+        let mut f = Self { mant, exp, prec };
+        f.normalize();
+        f
+    }
+
+    fn normalize(&mut self) {
+        if self.mant.is_zero() {
+            self.exp = 0;
+            return;
+        }
+        let bits = self.mant.bits() as usize;
+        if bits > self.prec {
+            let shift = bits - self.prec;
+            self.mant = round_shr(&self.mant, shift);
+            self.exp += shift as i64;
+        }
+        let tz = self.mant.trailing_zeros().unwrap_or(0);
+        if tz > 0 {
+            self.mant >>= tz as usize;
+            self.exp += tz as i64;
+        }
+    }
+
+    pub fn zero(prec: usize) -> Self {
+        Self::new(BigInt::zero(), 0, prec)
+    }
+
+    pub fn one(prec: usize) -> Self {
+        Self::new(BigInt::one(), 0, prec)
+    }
+
+    pub fn from_big_int(b: BigInt, prec: usize) -> Self {
+        Self::new(b, 0, prec)
+    }
+
+    pub fn from_big_rat(r: &BigRational, prec: usize) -> Self {
+        let (n, d) = (r.numer(), r.denom());
+        if n.is_zero() {
+            return Self::zero(prec);
+        }
+        let shift =
+            (prec + 2 + d.bits() as usize).saturating_sub(n.bits() as usize);
+        let n = n << shift;
+        // round half away from zero
+        let q = (n.abs() * 2u32 + d) / (d * 2u32);
+        let q = if r.is_negative() { -q } else { q };
+        Self::new(q, -(shift as i64), prec)
+    }
+
+    /// converts `f` exactly, or returns an error if it is infinite or NaN
+    pub fn from_f64(f: f64, prec: usize) -> Result<Self, Error> {
+        if !f.is_finite() {
+            errorf!("cannot convert {f} to a float");
+        }
+        let (mant, exp, sign) = num::Float::integer_decode(f);
+        Ok(Self::new(BigInt::from(sign) * mant, exp.into(), prec))
+    }
+
+    /// returns the nearest f64, which may be infinite
+    pub fn to_f64(&self) -> f64 {
+        let bits = self.mant.bits() as i64;
+        let drop = (bits - 64).max(0);
+        let m = round_shr(&self.mant, drop as usize).to_f64().unwrap();
+        let mut e = self.exp + drop;
+        let mut f = m;
+        // scale in steps so the intermediate powers stay finite
+        while e != 0 && f != 0.0 && f.is_finite() {
+            let step = e.clamp(-1000, 1000);
+            f *= 2f64.powi(step as i32);
+            e -= step;
+        }
+        f
+    }
+
+    /// returns the exact value as a rational
+    pub fn to_big_rat(&self) -> BigRational {
+        if self.exp >= 0 {
+            BigRational::from_integer(&self.mant << self.exp as usize)
+        } else {
+            let d = BigInt::one() << self.exp.unsigned_abs() as usize;
+            BigRational::new(self.mant.clone(), d)
+        }
+    }
+
+    /// returns the integer part, truncating towards zero
+    pub fn to_big_int(&self) -> BigInt {
+        if self.exp >= 0 {
+            &self.mant << self.exp as usize
+        } else {
+            let mag = self.mant.abs() >> self.exp.unsigned_abs() as usize;
+            if self.mant.is_negative() {
+                -mag
+            } else {
+                mag
+            }
+        }
+    }
+
+    /// returns the largest integer less than or equal to the value
+    pub fn floor(&self) -> BigInt {
+        let i = self.to_big_int();
+        if self.is_negative() && !self.is_int() {
+            i - 1
+        } else {
+            i
+        }
+    }
+
+    /// reports whether the value is an integer
+    pub fn is_int(&self) -> bool {
+        self.exp >= 0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mant.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.mant.is_negative()
+    }
+
+    pub fn prec(&self) -> usize {
+        self.prec
+    }
+
+    /// returns the number of bits in the integer part of the value, which is
+    /// negative for values less than 1/2 in magnitude
+    pub(crate) fn int_bits(&self) -> i64 {
+        self.mant.bits() as i64 + self.exp
+    }
+
+    /// returns the value, or an error if it is too large to represent. A
+    /// value too small to represent becomes zero.
+    fn checked(self) -> Result<Self, Error> {
+        if self.int_bits() > MAX_EXP {
+            errorf!("float overflow");
+        }
+        if self.int_bits() < -MAX_EXP {
+            return Ok(Self::zero(self.prec));
+        }
+        Ok(self)
+    }
+
+    /// returns a copy of the value rounded to `prec` bits
+    pub fn with_prec(&self, prec: usize) -> Self {
+        Self::new(self.mant.clone(), self.exp, prec)
+    }
+
+    pub fn abs(&self) -> Self {
+        Self::new(self.mant.abs(), self.exp, self.prec)
+    }
+
+    /// returns the value in fixed point with `wp` fractional bits
+    fn to_fixed(&self, wp: usize) -> BigInt {
+        shift(&self.mant, self.exp + wp as i64)
+    }
+
+    fn from_fixed(x: BigInt, wp: usize, prec: usize) -> Self {
+        Self::new(x, -(wp as i64), prec)
+    }
+
+    /// returns the product, without checking that it is in range
+    fn product(&self, other: &Self) -> Self {
+        let prec = self.prec.max(other.prec);
+        Self::new(&self.mant * &other.mant, self.exp + other.exp, prec)
+    }
+
+    /// returns the quotient by the nonzero `other`, without checking that it
+    /// is in range
+    fn quotient(&self, other: &Self) -> Self {
+        let prec = self.prec.max(other.prec);
+        let shift = (prec + 2 + other.mant.bits() as usize)
+            .saturating_sub(self.mant.bits() as usize);
+        let n = &self.mant << shift;
+        let d = &other.mant;
+        // round half away from zero
+        let q = (n.abs() * 2u32 + d.abs()) / (d.abs() * 2u32);
+        let q = if n.is_negative() != d.is_negative() {
+            -q
+        } else {
+            q
+        };
+        Self::new(q, self.exp - other.exp - shift as i64, prec)
+    }
+
+    /// returns pi to `prec` bits
+    pub fn pi(prec: usize) -> Self {
+        Self::from_fixed(pi_fixed(prec + GUARD), prec + GUARD, prec)
+    }
+
+    /// returns e to `prec` bits
+    pub fn e(prec: usize) -> Self {
+        let one = Self::one(prec + GUARD);
+        one.exp().expect("e is finite").with_prec(prec)
+    }
+
+    /// returns the value raised to the integer power `n`, or an error if the
+    /// result is too large to represent
+    pub fn powi(&self, n: i64) -> Result<Self, Error> {
+        let wp = self.prec + GUARD;
+        let mut base = self.with_prec(wp);
+        if n < 0 {
+            base = (&Self::one(wp) / &base)?;
+        }
+        let mut res = Self::one(wp);
+        let mut k = n.unsigned_abs();
+        while k > 0 {
+            if k & 1 == 1 {
+                res = (&res * &base)?;
+            }
+            k >>= 1;
+            if k > 0 {
+                base = (&base * &base)?;
+            }
+        }
+        Ok(res.with_prec(self.prec))
+    }
+
+    /// returns the square root, or an error if the value is negative
+    pub fn sqrt(&self) -> Result<Self, Error> {
+        if self.is_negative() {
+            errorf!("square root of negative number");
+        }
+        if self.is_zero() {
+            return Ok(self.clone());
+        }
+        let (mut m, mut e) = (self.mant.clone(), self.exp);
+        if e % 2 != 0 {
+            m <<= 1;
+            e -= 1;
+        }
+        // scale so the root has at least prec + 2 bits
+        let want = 2 * (self.prec + 2);
+        let k = want.saturating_sub(m.bits() as usize).div_ceil(2);
+        let s = (m << (2 * k)).sqrt();
+        Ok(Self::new(s, e / 2 - k as i64, self.prec))
+    }
+
+    /// returns e raised to the value, or an error if the result is too large
+    /// to represent
+    pub fn exp(&self) -> Result<Self, Error> {
+        if self.is_zero() {
+            return Ok(Self::one(self.prec));
+        }
+        // x = k ln 2 + r with |r| <= ln(2)/2, so exp(x) = 2**k exp(r)
+        let approx = self.to_f64() / std::f64::consts::LN_2;
+        if approx > MAX_EXP as f64 {
+            errorf!("exp overflow");
+        }
+        if approx < -MAX_EXP as f64 {
+            return Ok(Self::zero(self.prec));
+        }
+        let k = approx.round() as i64;
+        let wp = self.prec
+            + GUARD
+            + (64 - k.unsigned_abs().leading_zeros()) as usize;
+        let r = self.to_fixed(wp) - ln2_fixed(wp) * k;
+        Self::new(exp_fixed(&r, wp), k - wp as i64, self.prec).checked()
+    }
+
+    /// returns the natural logarithm, or an error if the value is not
+    /// positive
+    pub fn ln(&self) -> Result<Self, Error> {
+        if self.is_negative() || self.is_zero() {
+            errorf!("logarithm of non-positive value");
+        }
+        // x = m 2**k with m in [1, 2), so ln x = ln m + k ln 2, and
+        // ln m = 2 atanh((m-1)/(m+1))
+        let k = self.int_bits() - 1;
+        let wp = self.prec
+            + GUARD
+            + (64 - k.unsigned_abs().leading_zeros()) as usize;
+        let m = shift(&self.mant, self.exp - k + wp as i64);
+        let one = BigInt::one() << wp;
+        let z = ((&m - &one) << wp) / (&m + &one);
+        let ln = 2 * atanh_fixed(&z, wp) + ln2_fixed(wp) * k;
+        Ok(Self::from_fixed(ln, wp, self.prec))
+    }
+
+    /// returns the value raised to the power `y`, which may be non-integral.
+    /// It is an error if the value is negative and `y` is not an integer, or
+    /// if the result is too large to represent.
+    pub fn pow(&self, y: &Self) -> Result<Self, Error> {
+        if self.is_zero() && !y.is_zero() {
+            if y.is_negative() {
+                errorf!("division by zero");
+            }
+            return Ok(self.clone());
+        }
+        if y.is_int() {
+            if let Some(n) = y.to_big_int().to_i64() {
+                return self.powi(n);
+            }
+        }
+        let prec = self.prec.max(y.prec);
+        let wp = prec + GUARD + y.int_bits().max(0) as usize;
+        let ln = self.with_prec(wp).ln()?;
+        Ok((&ln * &y.with_prec(wp))?.exp()?.with_prec(prec))
+    }
+
+    /// returns `(sin(x), cos(x))`, or an error if the argument is too large
+    /// to reduce
+    fn sin_cos(&self) -> Result<(Self, Self), Error> {
+        if self.int_bits() > MAX_TRIG_BITS {
+            errorf!("argument too large for sin, cos or tan");
+        }
+        // reduce the argument into [-pi, pi]
+        let wp = self.prec + GUARD + self.int_bits().max(0) as usize;
+        let two_pi = pi_fixed(wp) * 2u32;
+        let x = self.to_fixed(wp);
+        let n = (&x * 2u32 + &two_pi).div_floor(&(&two_pi * 2u32));
+        let r = x - n * two_pi;
+        let (s, c) = sin_cos_fixed(&r, wp);
+        Ok((
+            Self::from_fixed(s, wp, self.prec),
+            Self::from_fixed(c, wp, self.prec),
+        ))
+    }
+
+    pub fn sin(&self) -> Result<Self, Error> {
+        Ok(self.sin_cos()?.0)
+    }
+
+    pub fn cos(&self) -> Result<Self, Error> {
+        Ok(self.sin_cos()?.1)
+    }
+
+    pub fn tan(&self) -> Result<Self, Error> {
+        let wp = self.with_prec(self.prec + GUARD);
+        let (s, c) = wp.sin_cos()?;
+        if c.is_zero() {
+            errorf!("tangent is infinite");
+        }
+        Ok((&s / &c)?.with_prec(self.prec))
+    }
+
+    pub fn atan(&self) -> Result<Self, Error> {
+        if self.is_zero() {
+            return Ok(self.clone());
+        }
+        const HALVINGS: usize = 4;
+        let wp = self.prec + GUARD + HALVINGS;
+        let one = Self::one(wp);
+        let x = self.with_prec(wp);
+        // for |x| > 1, atan(x) = ±pi/2 - atan(1/x)
+        let big = x.abs() > one;
+        let mut x = if big { (&one / &x)? } else { x };
+        // atan(x) = 2 atan(x / (1 + sqrt(1 + x**2)))
+        for _ in 0..HALVINGS {
+            x = (&x / &(&one + &(&one + &(&x * &x)?).sqrt()?))?;
+        }
+        let a = atan_fixed(&x.to_fixed(wp), wp) << HALVINGS;
+        let mut a = Self::from_fixed(a, wp, wp);
+        if big {
+            let half_pi = Self::from_fixed(pi_fixed(wp), wp + 1, wp);
+            a = if self.is_negative() {
+                &-&half_pi - &a
+            } else {
+                &half_pi - &a
+            };
+        }
+        Ok(a.with_prec(self.prec))
+    }
+
+    /// returns the arcsine, or an error if the value is outside [-1, 1]
+    pub fn asin(&self) -> Result<Self, Error> {
+        let wp = self.prec + GUARD;
+        let one = Self::one(wp);
+        let x = self.with_prec(wp);
+        match x.abs().cmp(&one) {
+            Ordering::Greater => errorf!("asin argument out of range"),
+            Ordering::Equal => {
+                let half_pi = Self::from_fixed(pi_fixed(wp), wp + 1, self.prec);
+                if self.is_negative() {
+                    Ok(-half_pi)
+                } else {
+                    Ok(half_pi)
+                }
+            }
+            Ordering::Less => {
+                let c = (&one - &(&x * &x)?).sqrt()?;
+                Ok((&x / &c)?.atan()?.with_prec(self.prec))
+            }
+        }
+    }
+
+    /// returns the arccosine, or an error if the value is outside [-1, 1]
+    pub fn acos(&self) -> Result<Self, Error> {
+        let wp = self.prec + GUARD;
+        let half_pi = Self::from_fixed(pi_fixed(wp), wp + 1, wp);
+        Ok((&half_pi - &self.with_prec(wp).asin()?).with_prec(self.prec))
+    }
+
+    /// formats the value like Go's `%.*g` verb with `digits` significant
+    /// digits
+    pub fn format_g(&self, digits: usize) -> String {
+        let digits = digits.max(1);
+        if self.is_zero() {
+            return String::from("0");
+        }
+        let (ds, e) = self.decimal_digits(digits);
+        let ds = ds.trim_end_matches('0');
+        let nd = ds.len() as i64;
+        let dp = e + 1;
+        let mut eprec = digits as i64;
+        if eprec > nd && nd >= dp {
+            eprec = nd;
+        }
+        let sign = if self.is_negative() { "-" } else { "" };
+        if e < -4 || e >= eprec {
+            let (first, rest) = ds.split_at(1);
+            let dot = if rest.is_empty() { "" } else { "." };
+            let esign = if e < 0 { '-' } else { '+' };
+            return format!("{sign}{first}{dot}{rest}e{esign}{:02}", e.abs());
+        }
+        if dp <= 0 {
+            format!("{sign}0.{}{ds}", "0".repeat(dp.unsigned_abs() as usize))
+        } else if dp >= nd {
+            format!("{sign}{ds}{}", "0".repeat((dp - nd) as usize))
+        } else {
+            let (int, frac) = ds.split_at(dp as usize);
+            format!("{sign}{int}.{frac}")
+        }
+    }
+
+    /// formats the value like Go's `%.*e` verb with `prec` digits after the
+    /// decimal point
+    pub fn format_e(&self, prec: usize) -> String {
+        let (ds, e) = if self.is_zero() {
+            ("0".repeat(prec + 1), 0)
+        } else {
+            self.decimal_digits(prec + 1)
+        };
+        let sign = if self.is_negative() { "-" } else { "" };
+        let (first, rest) = ds.split_at(1);
+        let dot = if rest.is_empty() { "" } else { "." };
+        let esign = if e < 0 { '-' } else { '+' };
+        format!("{sign}{first}{dot}{rest}e{esign}{:02}", e.abs())
+    }
+
+    /// returns the first `n` significant decimal digits of the magnitude,
+    /// rounded, along with the decimal exponent of the first digit. The
+    /// scaling is done in floating point with enough bits for the digits, so
+    /// a huge exponent does not need a huge integer.
+    fn decimal_digits(&self, n: usize) -> (String, i64) {
+        let wp = 4 * n + 2 * GUARD;
+        let x = self.abs().with_prec(wp);
+        let ten = Self::from_big_int(BigInt::from(10), wp);
+        let mut e = ((self.int_bits() - 1) as f64 * std::f64::consts::LOG10_2)
+            .floor() as i64;
+        loop {
+            let k = n as i64 - 1 - e;
+            let mut scale = Self::one(wp);
+            let mut base = ten.clone();
+            let mut j = k.unsigned_abs();
+            while j > 0 {
+                if j & 1 == 1 {
+                    scale = scale.product(&base);
+                }
+                j >>= 1;
+                if j > 0 {
+                    base = base.product(&base);
+                }
+            }
+            let scaled = if k >= 0 {
+                x.product(&scale)
+            } else {
+                x.quotient(&scale)
+            };
+            let s = scaled.to_fixed(0).to_string();
+            match s.len().cmp(&n) {
+                Ordering::Greater => e += 1,
+                Ordering::Less => e -= 1,
+                Ordering::Equal => return (s, e),
+            }
+        }
+    }
+}
+
+impl PartialEq for BigFloat {
+    fn eq(&self, other: &Self) -> bool {
+        self.mant == other.mant && self.exp == other.exp
+    }
+}
+
+impl Eq for BigFloat {}
+
+impl PartialOrd for BigFloat {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigFloat {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self - other).mant.sign().cmp(&num::bigint::Sign::NoSign)
+    }
+}
+
+impl Display for BigFloat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.format_g(f.precision().unwrap_or(12)))
+    }
+}
+
+impl Neg for BigFloat {
+    type Output = BigFloat;
+
+    fn neg(self) -> Self::Output {
+        Self::new(-self.mant, self.exp, self.prec)
+    }
+}
+
+impl Neg for &BigFloat {
+    type Output = BigFloat;
+
+    fn neg(self) -> Self::Output {
+        -self.clone()
+    }
+}
+
+impl Add for &BigFloat {
+    type Output = BigFloat;
+
+    fn add(self, other: Self) -> Self::Output {
+        let prec = self.prec.max(other.prec);
+        if other.is_zero() {
+            return self.with_prec(prec);
+        }
+        if self.is_zero() {
+            return other.with_prec(prec);
+        }
+        // if one operand is too small to affect the other, skip the alignment,
+        // which could otherwise produce enormous integers
+        let gap = prec as i64 + 2;
+        if self.int_bits() - other.int_bits() > gap {
+            return self.with_prec(prec);
+        }
+        if other.int_bits() - self.int_bits() > gap {
+            return other.with_prec(prec);
+        }
+        let exp = self.exp.min(other.exp);
+        let a = &self.mant << (self.exp - exp) as usize;
+        let b = &other.mant << (other.exp - exp) as usize;
+        BigFloat::new(a + b, exp, prec)
+    }
+}
+
+impl Sub for &BigFloat {
+    type Output = BigFloat;
+
+    fn sub(self, other: Self) -> Self::Output {
+        self + &-other
+    }
+}
+
+impl Sub<BigFloat> for &BigFloat {
+    type Output = BigFloat;
+
+    fn sub(self, other: BigFloat) -> Self::Output {
+        self - &other
+    }
+}
+
+/// multiplication fails if the product is too large to represent
+impl Mul for &BigFloat {
+    type Output = Result<BigFloat, Error>;
+
+    fn mul(self, other: Self) -> Self::Output {
+        if self.exp.checked_add(other.exp).is_none() {
+            if self.exp < 0 {
+                return Ok(BigFloat::zero(self.prec.max(other.prec)));
+            }
+            errorf!("float overflow");
+        }
+        self.product(other).checked()
+    }
+}
+
+/// division fails if the divisor is zero or the quotient is too large to
+/// represent
+impl Div for &BigFloat {
+    type Output = Result<BigFloat, Error>;
+
+    fn div(self, other: Self) -> Self::Output {
+        if other.is_zero() {
+            errorf!("division by zero");
+        }
+        self.quotient(other).checked()
+    }
+}
+
+#[test]
+fn pi_digits() {
+    let pi = BigFloat::pi(1000);
+    assert_eq!(
+        pi.format_g(50),
+        "3.1415926535897932384626433832795028841971693993751"
+    );
+    let sqrt2 = BigFloat::from_big_int(BigInt::from(2), 256).sqrt().unwrap();
+    assert_eq!(sqrt2.format_g(12), "1.41421356237");
+    assert_eq!(BigFloat::e(256).ln().unwrap().format_g(70), "1");
+}
+
+#[test]
+fn out_of_range() {
+    let f = |x: f64| BigFloat::from_f64(x, 64).unwrap();
+    assert!(BigFloat::from_f64(f64::INFINITY, 64).is_err());
+    assert!(BigFloat::from_f64(f64::NAN, 64).is_err());
+    assert!(f(-1.0).sqrt().is_err());
+    assert!(f(0.0).ln().is_err());
+    assert!(f(2.0).asin().is_err());
+    assert!(f(2.0).acos().is_err());
+    assert!((&f(1.0) / &f(0.0)).is_err());
+    assert!(f(1e100).powi(100_000_000_000_000_000).is_err());
+    assert!(f(3.0).powi(1_000_000_000_000_000_000).is_err());
+    assert!(f(3.0).powi(-1_000_000_000_000_000_000).unwrap().is_zero());
+    assert!(f(2.0).powi(100).unwrap().is_int());
+    let huge = f(2.0).powi(100_000).unwrap();
+    assert!(huge.sin().is_err());
+    assert!(huge.tan().is_err());
+    assert!(f(2.0).powi(1000).unwrap().cos().is_ok());
+}
@@ -1,6 +1,9 @@
-use super::super::Value;
+use super::super::{index, operand, Value};
 
-use crate::exec::context::Context;
+use crate::{
+    error::{errorf, Error},
+    exec::context::Context,
+};
 
 #[derive(Clone, Debug)]
 pub struct Binary {
@@ -55,6 +58,36 @@ pub enum Expr {
     Nil,
 }
 
+impl Binary {
+    /// evaluates the assignment `left = right`, storing the value of the right
+    /// side in the variable or indexed elements on the left and returning it
+    fn assign(&self, context: &mut Context) -> Result<Value, Error> {
+        let value = self.right.eval(context)?;
+        self.left.assign(context, value.clone())?;
+        Ok(value)
+    }
+}
+
+impl Index {
+    /// evaluates the indices, right to left. An elided index is `None`.
+    fn eval_index(
+        &self,
+        context: &mut Context,
+    ) -> Result<Vec<Option<Value>>, Error> {
+        let mut index = self
+            .right
+            .iter()
+            .rev()
+            .map(|e| match e {
+                Expr::Nil => Ok(None),
+                e => e.eval(context).map(Some),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        index.reverse();
+        Ok(index)
+    }
+}
+
 impl From<Value> for Expr {
     fn from(value: Value) -> Self {
         Self::Value(value)
@@ -91,32 +124,147 @@ impl Expr {
         }
     }
 
+    /// returns ivy source text for the expression, which parses back to the
+    /// same expression
     pub fn prog_string(&self) -> String {
-        todo!();
+        match self {
+            Expr::Conditional { binary } => format!(
+                "{}: {}",
+                binary.left.prog_string(),
+                binary.right.prog_string()
+            ),
+            // a binary op takes everything to its right, but only a single
+            // operand to its left
+            Expr::Binary { binary } => format!(
+                "{} {} {}",
+                operand(binary.left.prog_string()),
+                binary.op,
+                binary.right.prog_string()
+            ),
+            Expr::VariableExpr { name, .. } => name.clone(),
+            Expr::Index { index } => {
+                let right: Vec<_> =
+                    index.right.iter().map(Expr::prog_string).collect();
+                let left = operand(index.left.prog_string());
+                format!("{left}[{}]", right.join("; "))
+            }
+            Expr::Unary { unary } => {
+                format!("{} {}", unary.op, unary.right.prog_string())
+            }
+            Expr::SliceExpr { exprs } => {
+                let elems: Vec<_> =
+                    exprs.iter().map(|e| operand(e.prog_string())).collect();
+                elems.join(" ")
+            }
+            Expr::Value(v) => v.prog_string(),
+            Expr::Nil => String::new(),
+        }
     }
 
     #[allow(unused)]
-    pub fn eval(&self, context: &Context) -> Value {
+    pub fn eval(&self, context: &mut Context) -> Result<Value, Error> {
         match self {
-            Expr::Conditional { binary } => todo!(),
+            Expr::Conditional { .. } => {
+                errorf!("conditional `:` is only allowed inside an op")
+            }
             Expr::Binary { binary: b } => {
                 if b.op == "=" {
-                    todo!()
+                    return b.assign(context);
+                }
+                let rhs = b.right.eval(context)?;
+                let lhs = b.left.eval(context)?;
+                context.eval_binary(lhs, &b.op, rhs)
+            }
+            Expr::VariableExpr { name, local } => {
+                let v = if *local >= 1 {
+                    context.local(*local)
+                } else {
+                    context.global(name)
+                };
+                match v {
+                    Some(v) => Ok(v.clone()),
+                    None if *local >= 1 => {
+                        errorf!("undefined local variable `{name}`")
+                    }
+                    None => errorf!("undefined variable `{name}`"),
                 }
-                let rhs = b.right.eval(context);
-                let lhs = b.left.eval(context);
-                return context.eval_binary(lhs, &b.op, rhs);
             }
-            Expr::VariableExpr { name, local } => todo!(),
-            Expr::Index { index } => todo!(),
+            Expr::Index { index: i } => {
+                let index = i.eval_index(context)?;
+                let left = i.left.eval(context)?;
+                index::index(left, &index, context.config().origin())
+            }
             Expr::Unary { unary: u } => {
-                return context.eval_unary(&u.op, u.right.eval(context));
+                let right = u.right.eval(context)?;
+                context.eval_unary(&u.op, right)
+            }
+            Expr::SliceExpr { exprs } => {
+                // evaluate right to left, like everything else
+                let mut v = exprs
+                    .iter()
+                    .rev()
+                    .map(|e| e.eval(context))
+                    .collect::<Result<Vec<_>, _>>()?;
+                v.reverse();
+                Ok(Value::Vector(v))
+            }
+            Expr::Value(v) => Ok(v.clone()),
+            Expr::Nil => errorf!("empty expression"),
+        }
+    }
+
+    /// stores `value` in the variable, or the elements of the indexed
+    /// variable, named by the expression
+    fn assign(&self, context: &mut Context, value: Value) -> Result<(), Error> {
+        match self {
+            Expr::VariableExpr { name, local } => {
+                if *local >= 1 {
+                    context.assign_local(*local, value);
+                } else {
+                    context.no_op(name)?;
+                    context.assign_global(name, value);
+                }
             }
-            Expr::SliceExpr { exprs } => todo!(),
-            Expr::Value(v) => return v.clone(),
-            Expr::Nil => todo!(),
+            Expr::Index { index: i } => {
+                let index = i.eval_index(context)?;
+                let left = i.left.eval(context)?;
+                let origin = context.config().origin();
+                let value = index::assign(left, &index, origin, value)?;
+                i.left.assign(context, value)?;
+            }
+            left => errorf!("cannot assign to `{}`", left.prog_string()),
         }
-        todo!();
+        Ok(())
+    }
+
+    /// calls `f` on the expression and then on every expression within it
+    pub fn visit(&mut self, f: &mut impl FnMut(&mut Expr)) {
+        f(self);
+        match self {
+            Expr::Conditional { binary } | Expr::Binary { binary } => {
+                binary.left.visit(f);
+                binary.right.visit(f);
+            }
+            Expr::Index { index } => {
+                index.left.visit(f);
+                for e in &mut index.right {
+                    e.visit(f);
+                }
+            }
+            Expr::Unary { unary } => unary.right.visit(f),
+            Expr::SliceExpr { exprs } => {
+                for e in exprs {
+                    e.visit(f);
+                }
+            }
+            Expr::VariableExpr { .. } | Expr::Value(_) | Expr::Nil => {}
+        }
+    }
+
+    /// reports whether the expression is an assignment, whose value is not
+    /// printed at top level
+    pub fn is_assignment(&self) -> bool {
+        matches!(self, Expr::Binary { binary } if binary.op == "=")
     }
 
     /// Returns `true` if the expr is [`Nil`].
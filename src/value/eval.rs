@@ -1,5 +1,11 @@
-use super::Value;
-use crate::exec::context::Context;
+use super::{
+    matrix::{shape_string, shaped},
+    Value,
+};
+use crate::{
+    error::{errorf, Error},
+    exec::context::Context,
+};
 
 #[derive(Debug)]
 pub struct ParseBuiltinError;
@@ -7,14 +13,248 @@ pub struct ParseBuiltinError;
 pub mod binary;
 pub mod unary;
 
-pub fn reduce<'a>(_c: &Context<'a>, _op: &str, _v: Value) -> Value {
-    todo!()
+/// returns the characters of `v` as a string, if it is a character or a
+/// vector of them
+fn string_of(v: &Value) -> Option<String> {
+    match v {
+        Value::Char(c) => Some(c.to_string()),
+        Value::Vector(v) => v
+            .iter()
+            .map(|e| match e {
+                Value::Char(c) => Some(*c),
+                _ => None,
+            })
+            .collect(),
+        _ => None,
+    }
 }
 
-pub fn scan<'a>(_c: &Context<'a>, _op: &str, _v: Value) -> Value {
-    todo!()
+/// reports whether the builtin binary `op` is associative, so that a scan can
+/// carry its running value forward instead of reducing every prefix
+fn is_associative(op: &str) -> bool {
+    matches!(op, "+" | "*" | "min" | "max" | "or" | "and" | "xor")
 }
 
-pub fn product<'a>(_c: &Context<'a>, _u: Value, _op: &str, _v: Value) -> Value {
-    todo!()
+/// returns the identity of the builtin binary `op`, the value that reducing an
+/// empty vector by it gives, if it has one
+fn identity(op: &str) -> Option<Value> {
+    match op {
+        "+" | "-" | "|" | "^" | "or" | "xor" | "!=" => Some(Value::Int(0)),
+        "*" | "/" | "**" | "and" | "==" => Some(Value::Int(1)),
+        "&" => Some(Value::Int(-1)),
+        _ => None,
+    }
+}
+
+/// evaluates `op/ v`, folding `op` right to left over a vector, or over each
+/// row of a matrix. An empty row reduces to the identity of `op`; without
+/// one, an empty vector is left as it is.
+pub fn reduce<'a>(
+    c: &mut Context<'a>,
+    op: &str,
+    v: Value,
+) -> Result<Value, Error> {
+    match v {
+        Value::Vector(v) if v.is_empty() => {
+            Ok(identity(op).unwrap_or(Value::Vector(v)))
+        }
+        Value::Vector(v) => reduce_row(c, op, v),
+        Value::Matrix(m) => {
+            let mut shape = m.shape().to_vec();
+            let stride = shape.pop().unwrap_or(0);
+            if let (0, Some(id)) = (stride, identity(op)) {
+                let rows = shape.iter().product();
+                return Ok(shaped(shape, vec![id; rows]));
+            }
+            if stride == 0 {
+                errorf!(
+                    "shape for matrix is degenerate: {}",
+                    shape_string(m.shape())
+                );
+            }
+            let data = m
+                .into_data()
+                .chunks(stride)
+                .map(|row| reduce_row(c, op, row.to_vec()))
+                .collect::<Result<_, _>>()?;
+            Ok(shaped(shape, data))
+        }
+        v => Ok(v),
+    }
+}
+
+/// folds `op` right to left over the non-empty `row`
+fn reduce_row<'a>(
+    c: &mut Context<'a>,
+    op: &str,
+    mut row: Vec<Value>,
+) -> Result<Value, Error> {
+    let mut acc = row.pop().expect("row is not empty");
+    while let Some(v) = row.pop() {
+        acc = c.eval_binary(v, op, acc)?;
+    }
+    Ok(acc)
+}
+
+/// evaluates `op\ v`, whose elements are the reductions of the successive
+/// prefixes of a vector, or of each row of a matrix
+pub fn scan<'a>(
+    c: &mut Context<'a>,
+    op: &str,
+    v: Value,
+) -> Result<Value, Error> {
+    match v {
+        Value::Vector(v) => Ok(Value::Vector(scan_row(c, op, &v)?)),
+        Value::Matrix(m) => {
+            let stride = m.shape().last().copied().unwrap_or(0);
+            if stride == 0 {
+                return Ok(Value::Matrix(m));
+            }
+            let mut data = Vec::with_capacity(m.data().len());
+            for row in m.data().chunks(stride) {
+                data.extend(scan_row(c, op, row)?);
+            }
+            Ok(shaped(m.shape().to_vec(), data))
+        }
+        v => Ok(v),
+    }
+}
+
+fn scan_row<'a>(
+    c: &mut Context<'a>,
+    op: &str,
+    row: &[Value],
+) -> Result<Vec<Value>, Error> {
+    let mut values: Vec<Value> = Vec::with_capacity(row.len());
+    for (i, v) in row.iter().enumerate() {
+        let v = match values.last() {
+            None => v.clone(),
+            // this is O(n²) in general, but O(n) for associative ops
+            Some(prev) if is_associative(op) => {
+                c.eval_binary(prev.clone(), op, v.clone())?
+            }
+            Some(_) => reduce_row(c, op, row[..=i].to_vec())?,
+        };
+        values.push(v);
+    }
+    Ok(values)
+}
+
+/// evaluates the inner product `u op1.op2 v` or, if `op1` is `o`, the outer
+/// product `u o.op2 v`
+pub fn product<'a>(
+    c: &mut Context<'a>,
+    u: Value,
+    op: &str,
+    v: Value,
+) -> Result<Value, Error> {
+    let Some((op1, op2)) = op.split_once('.') else {
+        errorf!("bad product operator `{op}`");
+    };
+    if op1 == "o" {
+        return outer_product(c, u, op2, v);
+    }
+    inner_product(c, u, op1, op2, v)
+}
+
+/// applies `op` to every pair of elements of `u` and `v`. The shape of the
+/// result is the shape of `u` followed by that of `v`.
+fn outer_product<'a>(
+    c: &mut Context<'a>,
+    u: Value,
+    op: &str,
+    v: Value,
+) -> Result<Value, Error> {
+    let mut shape = u.shape();
+    shape.extend(v.shape());
+    let (u, v) = (u.into_elems(), v.into_elems());
+    let mut data = Vec::with_capacity(u.len() * v.len());
+    for a in &u {
+        for b in &v {
+            data.push(c.eval_binary(a.clone(), op, b.clone())?);
+        }
+    }
+    Ok(shaped(shape, data))
+}
+
+/// combines the last axis of `u` with the first axis of `v` by `op2`, and
+/// reduces each combination by `op1`, as in matrix multiplication with `+.*`.
+/// If either side is a scalar, this is `op1/ u op2 v`. Empty axes give the
+/// identity of `op1` everywhere.
+fn inner_product<'a>(
+    c: &mut Context<'a>,
+    u: Value,
+    op1: &str,
+    op2: &str,
+    v: Value,
+) -> Result<Value, Error> {
+    let (us, vs) = (u.shape(), v.shape());
+    let (Some(&n), Some(&m)) = (us.last(), vs.first()) else {
+        let w = c.eval_binary(u, op2, v)?;
+        return reduce(c, op1, w);
+    };
+    let id = identity(op1).filter(|_| n == 0);
+    if n != m || (n == 0 && id.is_none()) {
+        errorf!(
+            "inner product: mismatched dimensions {} and {}",
+            shape_string(&us),
+            shape_string(&vs)
+        );
+    }
+    let shape: Vec<_> =
+        us[..us.len() - 1].iter().chain(&vs[1..]).copied().collect();
+    if let Some(id) = id {
+        let len = shape.iter().product();
+        return Ok(shaped(shape, vec![id; len]));
+    }
+    let (u, v) = (u.into_elems(), v.into_elems());
+    // each row of u, of length n, pairs with each column of v, with stride cols
+    let (rows, cols) = (u.len() / n, v.len() / n);
+    let mut data = Vec::with_capacity(rows * cols);
+    for row in 0..rows {
+        for col in 0..cols {
+            let list = (0..n)
+                .map(|k| {
+                    let (a, b) = (&u[row * n + k], &v[k * cols + col]);
+                    c.eval_binary(a.clone(), op2, b.clone())
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            data.push(reduce(c, op1, Value::Vector(list))?);
+        }
+    }
+    Ok(shaped(shape, data))
+}
+
+/// returns the vector of the integers in `v`
+#[cfg(test)]
+pub(crate) fn ints(v: &[i64]) -> Value {
+    Value::Vector(v.iter().copied().map(Value::Int).collect())
+}
+
+#[test]
+fn reductions_and_products() {
+    use crate::config::Config;
+    let conf = Config::default();
+    let mut c = Context::new(&conf);
+    // reduction and scan run right to left
+    assert_eq!(reduce(&mut c, "-", ints(&[1, 2, 3, 4])), Ok(Value::Int(-2)));
+    assert_eq!(
+        scan(&mut c, "-", ints(&[1, 2, 3, 4])),
+        Ok(ints(&[1, -1, 2, -2]))
+    );
+    let m = shaped(vec![2, 2], ints(&[1, 2, 3, 4]).into_elems());
+    assert_eq!(reduce(&mut c, "+", m.clone()), Ok(ints(&[3, 7])));
+    let square = shaped(vec![2, 2], ints(&[7, 10, 15, 22]).into_elems());
+    assert_eq!(product(&mut c, m.clone(), "+.*", m), Ok(square));
+    let outer = product(&mut c, ints(&[1, 2]), "o.*", ints(&[3, 4]));
+    assert_eq!(outer.map(|v| v.shape()), Ok(vec![2, 2]));
+    // empty operands give the identity of the reducing op
+    assert_eq!(reduce(&mut c, "+", ints(&[])), Ok(Value::Int(0)));
+    assert_eq!(reduce(&mut c, "*", ints(&[])), Ok(Value::Int(1)));
+    assert_eq!(reduce(&mut c, "max", ints(&[])), Ok(ints(&[])));
+    let empty = product(&mut c, ints(&[]), "+.*", ints(&[]));
+    assert_eq!(empty, Ok(Value::Int(0)));
+    let rows = shaped(vec![2, 0], Vec::new());
+    assert_eq!(reduce(&mut c, "*", rows), Ok(ints(&[1, 1])));
+    assert!(product(&mut c, ints(&[]), "max.*", ints(&[])).is_err());
 }
@@ -1,12 +1,23 @@
-use super::super::Value;
+use num::{BigInt, Integer, One, Signed, ToPrimitive, Zero};
 
-use crate::exec::context::Context;
+use super::super::{
+    bigfloat::BigFloat,
+    format,
+    matrix::{self, shape_string},
+    Value,
+};
+
+use crate::{
+    config::Config,
+    error::{errorf, Error},
+    exec::context::Context,
+};
 
 use super::super::context::BinaryOp;
 
-use super::ParseBuiltinError;
+use super::{string_of, ParseBuiltinError};
 
-use std::str::FromStr;
+use std::{cmp::Ordering, fmt::Display, iter, str::FromStr};
 
 #[derive(Debug)]
 pub enum BinaryBuiltin {
@@ -15,8 +26,45 @@ pub enum BinaryBuiltin {
     Minus,
     Mul,
     Div,
+    IntDiv,
+    EuclidDiv,
     Mod,
     Exp,
+    Rho,
+    Deal,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+    And,
+    Or,
+    Xor,
+    Nand,
+    Nor,
+    Min,
+    Max,
+    Log,
+    Binomial,
+    IntMod,
+    Take,
+    Drop,
+    Catenate,
+    Rot,
+    Flip,
+    Fill,
+    Sel,
+    IndexOf,
+    In,
+    Encode,
+    Decode,
+    Text,
 }
 
 /// return whether or not `s` is a BinaryBuiltin
@@ -24,6 +72,13 @@ pub fn is_binary_op(s: &str) -> bool {
     BinaryBuiltin::from_str(s).is_ok()
 }
 
+/// the names of the binary builtins that are words rather than symbols
+pub const WORDS: &[&str] = &[
+    "j", "idiv", "div", "mod", "rho", "and", "or", "xor", "nand", "nor", "min",
+    "max", "log", "binomial", "imod", "take", "drop", "rot", "flip", "fill",
+    "sel", "iota", "in", "encode", "decode", "text",
+];
+
 impl FromStr for BinaryBuiltin {
     type Err = ParseBuiltinError;
 
@@ -34,83 +89,1211 @@ impl FromStr for BinaryBuiltin {
             "-" => Ok(Self::Minus),
             "*" => Ok(Self::Mul),
             "/" => Ok(Self::Div),
+            "idiv" => Ok(Self::IntDiv),
+            "div" => Ok(Self::EuclidDiv),
             "%" | "mod" => Ok(Self::Mod),
             "**" => Ok(Self::Exp),
+            "rho" => Ok(Self::Rho),
+            "?" => Ok(Self::Deal),
+            "==" => Ok(Self::Eq),
+            "!=" => Ok(Self::Ne),
+            "<" => Ok(Self::Lt),
+            "<=" => Ok(Self::Le),
+            ">" => Ok(Self::Gt),
+            ">=" => Ok(Self::Ge),
+            "&" => Ok(Self::BitAnd),
+            "|" => Ok(Self::BitOr),
+            "^" => Ok(Self::BitXor),
+            "<<" => Ok(Self::Shl),
+            ">>" => Ok(Self::Shr),
+            "and" => Ok(Self::And),
+            "or" => Ok(Self::Or),
+            "xor" => Ok(Self::Xor),
+            "nand" => Ok(Self::Nand),
+            "nor" => Ok(Self::Nor),
+            "min" => Ok(Self::Min),
+            "max" => Ok(Self::Max),
+            "log" => Ok(Self::Log),
+            "!" | "binomial" => Ok(Self::Binomial),
+            "imod" => Ok(Self::IntMod),
+            "take" => Ok(Self::Take),
+            "drop" => Ok(Self::Drop),
+            "," => Ok(Self::Catenate),
+            "rot" => Ok(Self::Rot),
+            "flip" => Ok(Self::Flip),
+            "fill" => Ok(Self::Fill),
+            "sel" => Ok(Self::Sel),
+            "iota" => Ok(Self::IndexOf),
+            "in" => Ok(Self::In),
+            "encode" => Ok(Self::Encode),
+            "decode" => Ok(Self::Decode),
+            "text" => Ok(Self::Text),
             _ => Err(ParseBuiltinError),
         }
     }
 }
 
-macro_rules! make_ops {
-    ($typ: path, $p1: pat_param, $p2: pat_param, $left: ident, $right: ident,
-     $expr: expr) => {
-        if let $typ($p1) = $left {
-            if let $typ($p2) = $right {
-                return $typ($expr);
+/// displays the operator as it is written in ivy
+impl Display for BinaryBuiltin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use BinaryBuiltin::*;
+        let name = match self {
+            NewComplex => "j",
+            Plus => "+",
+            Minus => "-",
+            Mul => "*",
+            Div => "/",
+            IntDiv => "idiv",
+            EuclidDiv => "div",
+            Mod => "mod",
+            Exp => "**",
+            Rho => "rho",
+            Deal => "?",
+            Eq => "==",
+            Ne => "!=",
+            Lt => "<",
+            Le => "<=",
+            Gt => ">",
+            Ge => ">=",
+            BitAnd => "&",
+            BitOr => "|",
+            BitXor => "^",
+            Shl => "<<",
+            Shr => ">>",
+            And => "and",
+            Or => "or",
+            Xor => "xor",
+            Nand => "nand",
+            Nor => "nor",
+            Min => "min",
+            Max => "max",
+            Log => "log",
+            Binomial => "!",
+            IntMod => "imod",
+            Take => "take",
+            Drop => "drop",
+            Catenate => ",",
+            Rot => "rot",
+            Flip => "flip",
+            Fill => "fill",
+            Sel => "sel",
+            IndexOf => "iota",
+            In => "in",
+            Encode => "encode",
+            Decode => "decode",
+            Text => "text",
+        };
+        f.write_str(name)
+    }
+}
+
+/// evaluates `$left $op $right` after promoting the operands to their common
+/// type with [promote]. Integers must be handled before reaching here. A `?`
+/// after the op marks one that can fail on floats.
+macro_rules! promoted {
+    ($self: ident, $ctx: ident, $left: ident, $op: tt ?, $right: ident) => {
+        promoted!(@ $self, $ctx, $left, $op, $right, ?)
+    };
+    ($self: ident, $ctx: ident, $left: ident, $op: tt, $right: ident) => {
+        promoted!(@ $self, $ctx, $left, $op, $right,)
+    };
+    (
+        @ $self: ident, $ctx: ident, $left: ident, $op: tt, $right: ident,
+        $($try: tt)?
+    ) => {
+        match promote($ctx, $left, $right) {
+            (Rational(i), Rational(j)) => Ok(Value::from(i $op j)),
+            (Float(i), Float(j)) => Ok(Value::from((&i $op &j)$($try)?)),
+            (Complex(i), Complex(j)) => {
+                Value::from_complex(i $op j, $ctx.config().float_prec())
             }
+            (l, r) => Err(bad_types($self, l, r)),
         }
     };
-    ($typ1: path, $p1: pat_param, $typ2: path, $p2: pat_param,
-     $left: ident, $right: ident, $ret_typ: path, $expr: expr) => {
-        if let $typ1($p1) = $left {
-            if let $typ2($p2) = $right {
-                return $ret_typ($expr);
+}
+
+/// the numeric types above the integers, in the order in which they are
+/// promoted
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Level {
+    Rational,
+    Float,
+    Complex,
+}
+
+/// returns the promotion level of `v`, or `None` if it is not a number
+fn level(v: &Value) -> Option<Level> {
+    match v {
+        Value::Int(_) | Value::BigInt(_) | Value::Rational(_) => {
+            Some(Level::Rational)
+        }
+        Value::Float(_) => Some(Level::Float),
+        Value::Complex(_) => Some(Level::Complex),
+        _ => None,
+    }
+}
+
+/// converts `left` and `right` to the same numeric type, the higher of the two
+/// in the order Int → BigInt → Rational → Float → Complex. Integers are
+/// promoted to at least `Rational`. Non-numeric values are returned unchanged.
+fn promote(ctx: &Context, left: Value, right: Value) -> (Value, Value) {
+    let (Some(l), Some(r)) = (level(&left), level(&right)) else {
+        return (left, right);
+    };
+    let prec = ctx.config().float_prec();
+    let lift = |v: Value| match l.max(r) {
+        Level::Rational => Value::Rational(v.to_big_rat().unwrap()),
+        Level::Float => Value::Float(v.to_big_float(prec).unwrap()),
+        Level::Complex => match v {
+            Value::Complex(c) => Value::Complex(c),
+            v => Value::Complex(num::Complex::new(v.to_f64().unwrap(), 0.0)),
+        },
+    };
+    (lift(left), lift(right))
+}
+
+/// returns the error reporting that `op` cannot be applied to values of these
+/// types
+fn bad_types(op: &BinaryBuiltin, left: Value, right: Value) -> Error {
+    Error::eval(format!(
+        "`{op}` not implemented for {} and {}",
+        left.type_name(),
+        right.type_name()
+    ))
+}
+
+/// returns an error if the product of `left` and `right` could have more bits
+/// than `conf` allows. Sums and quotients of rationals grow the same way.
+fn check_product(
+    conf: &Config,
+    left: &Value,
+    right: &Value,
+) -> Result<(), Error> {
+    conf.check_bits(left.bits() + right.bits())
+}
+
+/// applies `checked` to `left` and `right` if they are both integers, promoting
+/// to `BigInt` and applying `big` instead if `checked` overflows. The result is
+/// shrunk back to an `Int` when it fits. Returns `None` if either value is not
+/// an integer.
+fn int_op(
+    left: &Value,
+    right: &Value,
+    checked: fn(i64, i64) -> Option<i64>,
+    big: fn(BigInt, BigInt) -> BigInt,
+) -> Option<Value> {
+    if let (Value::Int(i), Value::Int(j)) = (left, right) {
+        if let Some(res) = checked(*i, *j) {
+            return Some(Value::Int(res));
+        }
+    }
+    let i = left.to_big_int()?;
+    let j = right.to_big_int()?;
+    Some(big(i, j).into())
+}
+
+/// returns the Euclidean remainder of `i` and `j`, which is never negative
+fn rem_euclid<T: Signed + Clone>(i: T, j: T) -> T {
+    let r = i % j.clone();
+    if r.is_negative() {
+        r + j.abs()
+    } else {
+        r
+    }
+}
+
+/// returns the Euclidean quotient of `i` and `j`, the partner of [rem_euclid]
+fn div_euclid(i: BigInt, j: BigInt) -> BigInt {
+    let r = rem_euclid(i.clone(), j.clone());
+    (i - r) / j
+}
+
+/// returns an error if `right` is zero, since `op` would divide by it
+fn check_zero(op: &BinaryBuiltin, right: &Value) -> Result<(), Error> {
+    if right.is_zero() {
+        errorf!("division by zero in `{op}`");
+    }
+    Ok(())
+}
+
+/// returns about how many bits `base ** exp` has, which is no more than one
+/// for a base of magnitude at most 1
+fn pow_bits(base: &BigInt, exp: u64) -> u64 {
+    // the top 64 bits are plenty for the logarithm
+    let shift = base.bits().saturating_sub(64);
+    let top = (base.magnitude() >> shift).to_f64().unwrap_or(0.0);
+    ((top.log2() + shift as f64) * exp as f64).max(0.0).ceil() as u64
+}
+
+/// raises the integer `left` to the integer power `right`, returning `None` if
+/// either value is not an integer. Negative powers yield the reciprocal. It
+/// is an error if the result would have more bits than `conf` allows.
+fn int_pow(
+    conf: &Config,
+    left: &Value,
+    right: &Value,
+) -> Result<Option<Value>, Error> {
+    if let (Value::Int(i), Value::Int(j)) = (left, right) {
+        if let Ok(j) = u32::try_from(*j) {
+            if let Some(res) = i.checked_pow(j) {
+                return Ok(Some(Value::Int(res)));
             }
         }
+    }
+    let (Some(i), Some(j)) = (left.to_big_int(), right.to_big_int()) else {
+        return Ok(Option::None);
+    };
+    let Ok(exp) = u32::try_from(j.abs()) else {
+        errorf!("exponent {j} too large");
+    };
+    conf.check_bits(pow_bits(&i, exp.into()))?;
+    let res = num::pow(i, exp as usize);
+    if j.is_negative() {
+        return Value::rational(Value::Int(1), res.into()).map(Some);
+    }
+    Ok(Some(res.into()))
+}
+
+/// raises the real `left` to the real power `right` in floating point,
+/// returning `None` if either value is not real. Negative bases with
+/// non-integral exponents yield complex results.
+fn float_pow(
+    ctx: &Context,
+    left: &Value,
+    right: &Value,
+) -> Result<Option<Value>, Error> {
+    let prec = ctx.config().float_prec();
+    let (Some(x), Some(y)) =
+        (left.to_big_float(prec), right.to_big_float(prec))
+    else {
+        return Ok(Option::None);
+    };
+    if x.is_negative() && !y.is_int() {
+        let z = num::Complex::new(x.to_f64(), 0.0).powf(y.to_f64());
+        return Value::from_complex(z, prec).map(Some);
+    }
+    Ok(Some(x.pow(&y)?.into()))
+}
+
+/// returns the value of a comparison or logical operator: 1 for true and 0
+/// for false
+fn truth(b: bool) -> Value {
+    Value::Int(b as i64)
+}
+
+/// returns the truth of a number for the logical operators, which treat every
+/// non-zero number as true. Returns `None` if `v` is not a number.
+fn boolean(v: &Value) -> Option<bool> {
+    level(v).map(|_| !v.is_zero())
+}
+
+/// returns the number `v` as a complex f64, or `None` if it is not a number
+fn to_complex(v: &Value) -> Option<num::Complex<f64>> {
+    match v {
+        Value::Complex(c) => Some(*c),
+        v => v.to_f64().map(|re| num::Complex::new(re, 0.0)),
+    }
+}
+
+/// reports whether two scalars are equal: numbers by value whatever their
+/// types, and characters by code point. A character never equals a number.
+fn equal(left: &Value, right: &Value) -> bool {
+    if left.is_complex() || right.is_complex() {
+        return to_complex(left).is_some_and(|l| to_complex(right) == Some(l));
+    }
+    left.compare(right) == Some(Ordering::Equal)
+}
+
+/// returns the number of ways of choosing `k` items from `n`, extended to
+/// negative `n` by the identity C(n, k) = (-1)^k C(k-n-1, k). It is an error
+/// if `k` is too large to count up to, or if the result could have more bits
+/// than `conf` allows.
+fn binomial(conf: &Config, k: BigInt, n: BigInt) -> Result<BigInt, Error> {
+    if k.is_negative() {
+        return Ok(BigInt::zero());
+    }
+    if n.is_negative() {
+        let c = binomial(conf, k.clone(), &k - n - 1)?;
+        return Ok(if k.is_odd() { -c } else { c });
+    }
+    if k > n {
+        return Ok(BigInt::zero());
+    }
+    let Some(k) = k.clone().min(&n - &k).to_u64() else {
+        errorf!("binomial: {k} is too large");
+    };
+    // C(n, k) is at most n**k, and at most 2**n
+    let bound = k.saturating_mul(n.bits());
+    conf.check_bits(bound.min(n.to_u64().unwrap_or(u64::MAX)))?;
+    let mut c = BigInt::one();
+    for i in 0..k {
+        c = c * (&n - i) / (i + 1);
+    }
+    Ok(c)
+}
+
+/// returns the scalar integer `v`, the left operand of `op`
+fn count(op: &str, v: &Value) -> Result<i64, Error> {
+    match v {
+        Value::Int(i) => Ok(*i),
+        v => errorf!("{op}: bad count {v}"),
+    }
+}
+
+/// returns the left operand of `op` as a list of non-negative integer counts
+fn counts(op: &str, v: Value) -> Result<Vec<usize>, Error> {
+    v.into_elems()
+        .into_iter()
+        .map(|c| match c {
+            Value::Int(i) if i >= 0 => Ok(i as usize),
+            c => errorf!("{op}: bad count {c}"),
+        })
+        .collect()
+}
+
+/// splits `v` into its items along the first axis, returning the shape of an
+/// item and the elements of each. A scalar is a single item.
+fn items(v: Value) -> (Vec<usize>, Vec<Vec<Value>>) {
+    let shape = v.shape();
+    let cell = shape.iter().skip(1).copied().collect::<Vec<_>>();
+    let size = cell.iter().product::<usize>();
+    let n = shape.first().copied().unwrap_or(1);
+    let mut data = v.into_elems().into_iter();
+    let items = (0..n).map(|_| data.by_ref().take(size).collect()).collect();
+    (cell, items)
+}
+
+/// joins `items`, each of shape `cell`, into a value along a new first axis.
+/// The result is always a vector or matrix, even if there is one item.
+fn from_items(cell: &[usize], items: Vec<Vec<Value>>) -> Value {
+    let shape = iter::once(items.len())
+        .chain(cell.iter().copied())
+        .collect();
+    matrix::shaped(shape, items.into_iter().flatten().collect())
+}
+
+/// splits `v` into its rows along the last axis. A scalar is a single row of
+/// one element.
+fn rows(v: Value) -> (Vec<usize>, Vec<Vec<Value>>) {
+    let mut shape = v.shape();
+    let cols = shape.pop().unwrap_or(1);
+    let data = v.into_elems();
+    let rows = match cols {
+        0 => vec![Vec::new(); shape.iter().product()],
+        _ => data.chunks(cols).map(<[Value]>::to_vec).collect(),
+    };
+    (shape, rows)
+}
+
+/// joins `rows` of equal length into a value, the leading dimensions of which
+/// are `shape`
+fn from_rows(mut shape: Vec<usize>, rows: Vec<Vec<Value>>) -> Value {
+    shape.push(rows.first().map_or(0, Vec::len));
+    matrix::shaped(shape, rows.into_iter().flatten().collect())
+}
+
+/// joins `left` and `right` along their last axis. Values of rank at most one
+/// join into a vector. Otherwise the leading dimensions of the operands must
+/// match, except that a scalar, or a value whose shape is the leading
+/// dimensions of the other, is joined as a single column.
+fn catenate(left: Value, right: Value) -> Result<Value, Error> {
+    let (lshape, rshape) = (left.shape(), right.shape());
+    if lshape.len() <= 1 && rshape.len() <= 1 {
+        let mut data = left.into_elems();
+        data.extend(right.into_elems());
+        return Ok(Value::Vector(data));
+    }
+    let shape = if lshape.len() >= rshape.len() {
+        &lshape
+    } else {
+        &rshape
+    };
+    let lead = shape[..shape.len() - 1].to_vec();
+    let n = lead.iter().product();
+    let columns = |v: Value| match v.shape() {
+        s if s.is_empty() => Some(vec![vec![v]; n]),
+        s if s == lead => {
+            Some(v.into_elems().into_iter().map(|e| vec![e]).collect())
+        }
+        s if s[..s.len() - 1] == lead => Some(rows(v).1),
+        _ => Option::None,
+    };
+    let (Some(left), Some(right)) = (columns(left), columns(right)) else {
+        errorf!(
+            "catenate: shape mismatch: {} and {}",
+            shape_string(&lshape),
+            shape_string(&rshape)
+        );
     };
+    let rows = iter::zip(left, right)
+        .map(|(l, r)| [l, r].concat())
+        .collect();
+    Ok(from_rows(lead, rows))
+}
+
+/// returns the element used to pad `v`: a space if it holds characters and
+/// zero otherwise
+fn fill_elem(v: &Value) -> Value {
+    match v.clone().into_elems().first() {
+        Some(Value::Char(_)) => Value::Char(' '),
+        _ => Value::Int(0),
+    }
+}
+
+impl BinaryBuiltin {
+    /// reports whether the operator applies to each element of its operands
+    /// separately, rather than to their shapes as a whole
+    fn is_elementwise(&self) -> bool {
+        use BinaryBuiltin::*;
+        !matches!(
+            self,
+            Rho | Deal
+                | Take
+                | Drop
+                | Catenate
+                | Rot
+                | Flip
+                | Fill
+                | Sel
+                | IndexOf
+                | In
+                | Encode
+                | Decode
+                | Text
+        )
+    }
+
+    /// applies the operator elementwise to `left` and `right`, at least one of
+    /// which is a vector or matrix. A scalar operand is paired with every
+    /// element of the other, two vectors must have the same length, and two
+    /// matrices must have the same shape. A vector paired with a matrix is
+    /// applied to each row, so its length must match the length of the rows.
+    fn eval_elementwise(
+        &self,
+        ctx: &mut Context,
+        left: Value,
+        right: Value,
+    ) -> Result<Value, Error> {
+        use Value::*;
+        let (lshape, rshape) = (left.shape(), right.shape());
+        let shape = if lshape.len() >= rshape.len() {
+            lshape.clone()
+        } else {
+            rshape.clone()
+        };
+        let conformable = match (lshape.len(), rshape.len()) {
+            (0, _) | (_, 0) => true,
+            (1, _) => lshape.last() == rshape.last(),
+            (_, 1) => lshape.last() == rshape.last(),
+            _ => lshape == rshape,
+        };
+        if !conformable {
+            errorf!(
+                "`{self}`: shape mismatch: {} and {}",
+                shape_string(&lshape),
+                shape_string(&rshape)
+            );
+        }
+        let n = shape.iter().product();
+        let cycle = |v: Value| -> Vec<Value> {
+            v.into_elems().into_iter().cycle().take(n).collect()
+        };
+        let data = cycle(left)
+            .into_iter()
+            .zip(cycle(right))
+            .map(|(l, r)| self.eval_binary(ctx, l, r))
+            .collect::<Result<_, _>>()?;
+        if shape.len() == 1 {
+            Ok(Vector(data))
+        } else {
+            Ok(Matrix(matrix::Matrix::new(shape, data)))
+        }
+    }
+
+    /// applies the operator to the operands as a whole
+    fn eval_shape(
+        &self,
+        ctx: &mut Context,
+        left: Value,
+        right: Value,
+    ) -> Result<Value, Error> {
+        use Value::*;
+        let origin = ctx.config().origin() as i64;
+        match self {
+            BinaryBuiltin::Rho => {
+                let shape = dimensions(left)?;
+                matrix::reshape(ctx.config(), shape, right.into_elems())
+            }
+            BinaryBuiltin::Deal => {
+                let (m, n) = (count("deal", &left)?, count("deal", &right)?);
+                if m < 0 || m > n {
+                    errorf!("deal: cannot deal {m} from {n}");
+                }
+                let rng = ctx.rng();
+                let picks =
+                    rand::seq::index::sample(rng, n as usize, m as usize);
+                let data = picks.into_iter().map(|i| Int(i as i64 + origin));
+                Ok(Vector(data.collect()))
+            }
+            BinaryBuiltin::Take => {
+                let n = count("take", &left)?;
+                let pad = fill_elem(&right);
+                let (cell, items) = items(right);
+                let pad = vec![pad; cell.iter().product()];
+                let (len, want) = (items.len(), n.unsigned_abs() as usize);
+                let padding = iter::repeat_n(pad, want.saturating_sub(len));
+                let taken = if n >= 0 {
+                    items.into_iter().take(want).chain(padding).collect()
+                } else {
+                    let skip = len.saturating_sub(want);
+                    padding.chain(items.into_iter().skip(skip)).collect()
+                };
+                Ok(from_items(&cell, taken))
+            }
+            BinaryBuiltin::Drop => {
+                let n = count("drop", &left)?;
+                let (cell, mut items) = items(right);
+                let k = (n.unsigned_abs() as usize).min(items.len());
+                if n >= 0 {
+                    items.drain(..k);
+                } else {
+                    items.truncate(items.len() - k);
+                }
+                Ok(from_items(&cell, items))
+            }
+            BinaryBuiltin::Catenate => catenate(left, right),
+            BinaryBuiltin::Rot => {
+                let n = count("rot", &left)?;
+                if right.shape().is_empty() {
+                    return Ok(right);
+                }
+                let (shape, mut rows) = rows(right);
+                for row in rows.iter_mut().filter(|r| !r.is_empty()) {
+                    let k = n.rem_euclid(row.len() as i64) as usize;
+                    row.rotate_left(k);
+                }
+                Ok(from_rows(shape, rows))
+            }
+            BinaryBuiltin::Flip => {
+                let n = count("flip", &left)?;
+                if right.shape().is_empty() {
+                    return Ok(right);
+                }
+                let (cell, mut items) = items(right);
+                if !items.is_empty() {
+                    let k = n.rem_euclid(items.len() as i64) as usize;
+                    items.rotate_left(k);
+                }
+                Ok(from_items(&cell, items))
+            }
+            BinaryBuiltin::Sel => {
+                let counts = counts("sel", left)?;
+                let (shape, rows) = rows(right);
+                let len = rows.first().map_or(0, Vec::len);
+                if counts.len() != 1 && counts.len() != len {
+                    errorf!(
+                        "sel: {} counts for axis of length {len}",
+                        counts.len()
+                    );
+                }
+                let rows = rows
+                    .into_iter()
+                    .map(|row| {
+                        iter::zip(row, counts.iter().cycle())
+                            .flat_map(|(e, &c)| iter::repeat_n(e, c))
+                            .collect()
+                    })
+                    .collect();
+                Ok(from_rows(shape, rows))
+            }
+            BinaryBuiltin::Fill => {
+                let counts = counts("fill", left)?;
+                let pad = fill_elem(&right);
+                let scalar = right.shape().is_empty();
+                let (shape, rows) = rows(right);
+                let len = rows.first().map_or(0, Vec::len);
+                let used = counts.iter().filter(|&&c| c > 0).count();
+                if !scalar && used != len {
+                    errorf!(
+                        "fill: {used} non-zero counts for axis of length {len}"
+                    );
+                }
+                let rows = rows
+                    .into_iter()
+                    .map(|row| {
+                        // a scalar is repeated for every non-zero count
+                        let mut elems = row.into_iter().cycle();
+                        counts
+                            .iter()
+                            .flat_map(|&c| match c {
+                                0 => vec![pad.clone()],
+                                c => vec![elems.next().unwrap(); c],
+                            })
+                            .collect()
+                    })
+                    .collect();
+                Ok(from_rows(shape, rows))
+            }
+            BinaryBuiltin::IndexOf => {
+                if left.is_matrix() {
+                    errorf!("iota: left operand must be a vector");
+                }
+                let list = left.into_elems();
+                let shape = right.shape();
+                let data = right
+                    .into_elems()
+                    .iter()
+                    .map(|e| {
+                        let pos = list.iter().position(|l| equal(l, e));
+                        Int(pos.map_or(origin - 1, |p| p as i64 + origin))
+                    })
+                    .collect();
+                Ok(matrix::shaped(shape, data))
+            }
+            BinaryBuiltin::In => {
+                let set = right.into_elems();
+                let shape = left.shape();
+                let data = left
+                    .into_elems()
+                    .iter()
+                    .map(|e| truth(set.iter().any(|s| equal(e, s))))
+                    .collect();
+                Ok(matrix::shaped(shape, data))
+            }
+            BinaryBuiltin::Encode => {
+                if left.shape().is_empty() {
+                    if left.is_zero() {
+                        return Ok(right);
+                    }
+                    return BinaryBuiltin::Mod.eval_binary(ctx, right, left);
+                }
+                let cell = right.shape();
+                let mut digits = Vec::new();
+                let mut rest = right;
+                for r in left.into_elems().into_iter().rev() {
+                    // a radix of zero keeps all that remains
+                    if r.is_zero() {
+                        digits.push(rest.clone());
+                        rest = BinaryBuiltin::Mul.eval_binary(ctx, rest, r)?;
+                        continue;
+                    }
+                    let d = BinaryBuiltin::Mod.eval_binary(
+                        ctx,
+                        rest.clone(),
+                        r.clone(),
+                    )?;
+                    rest = BinaryBuiltin::Minus.eval_binary(
+                        ctx,
+                        rest,
+                        d.clone(),
+                    )?;
+                    rest = BinaryBuiltin::Div.eval_binary(ctx, rest, r)?;
+                    digits.push(d);
+                }
+                digits.reverse();
+                let digits =
+                    digits.into_iter().map(Value::into_elems).collect();
+                Ok(from_items(&cell, digits))
+            }
+            BinaryBuiltin::Decode => {
+                let radices = left.into_elems();
+                let scalar = right.shape().is_empty();
+                let (cell, mut digits) = items(right);
+                if scalar {
+                    digits = vec![digits.remove(0); radices.len()];
+                }
+                if radices.len() != 1 && radices.len() != digits.len() {
+                    errorf!(
+                        "decode: {} radices for {} digits",
+                        radices.len(),
+                        digits.len()
+                    );
+                }
+                let mut acc = Int(0);
+                for (d, r) in iter::zip(digits, radices.iter().cycle()) {
+                    let d = matrix::shaped(cell.clone(), d);
+                    acc =
+                        BinaryBuiltin::Mul.eval_binary(ctx, acc, r.clone())?;
+                    acc = BinaryBuiltin::Plus.eval_binary(ctx, acc, d)?;
+                }
+                Ok(acc)
+            }
+            BinaryBuiltin::Text => {
+                let Some(fmt) = string_of(&left) else {
+                    errorf!("text: left operand must be a format string");
+                };
+                let prec = ctx.config().float_prec();
+                let text = format::sprintf(&fmt, &right, prec)?;
+                Ok(Vector(text.chars().map(Char).collect()))
+            }
+            _ => unreachable!("{self:?} is elementwise"),
+        }
+    }
+}
+
+/// returns `v` as a list of dimensions for `rho`, which must be non-negative
+/// integers
+fn dimensions(v: Value) -> Result<Vec<usize>, Error> {
+    v.into_elems()
+        .into_iter()
+        .map(|d| match d {
+            Value::Int(i) if i >= 0 => Ok(i as usize),
+            d => Err(Error::eval(format!("rho: bad dimension {d}"))),
+        })
+        .collect()
 }
 
 impl<'a> BinaryOp<'a> for BinaryBuiltin {
     fn eval_binary(
         &self,
-        _ctx: &Context<'a>,
+        ctx: &mut Context<'a>,
         left: Value,
         right: Value,
-    ) -> Value {
+    ) -> Result<Value, Error> {
         use Value::*;
+        if !self.is_elementwise() {
+            return self.eval_shape(ctx, left, right);
+        }
+        if !left.shape().is_empty() || !right.shape().is_empty() {
+            return self.eval_elementwise(ctx, left, right);
+        }
         match self {
             BinaryBuiltin::Plus => {
-                make_ops!(Int, i, j, left, right, i + j);
-                make_ops!(Rational, i, j, left, right, i + j);
+                if let Some(v) =
+                    int_op(&left, &right, i64::checked_add, |i, j| i + j)
+                {
+                    return Ok(v);
+                }
+                check_product(ctx.config(), &left, &right)?;
+                promoted!(self, ctx, left, +, right)
             }
             BinaryBuiltin::Minus => {
-                make_ops!(Int, i, j, left, right, i - j);
+                if let Some(v) =
+                    int_op(&left, &right, i64::checked_sub, |i, j| i - j)
+                {
+                    return Ok(v);
+                }
+                check_product(ctx.config(), &left, &right)?;
+                promoted!(self, ctx, left, -, right)
             }
-            BinaryBuiltin::NewComplex => return Value::complex(left, right),
+            BinaryBuiltin::NewComplex => Value::complex(left, right),
             BinaryBuiltin::Mul => {
-                make_ops!(Int, i, j, left, right, i * j);
+                check_product(ctx.config(), &left, &right)?;
+                if let Some(v) =
+                    int_op(&left, &right, i64::checked_mul, |i, j| i * j)
+                {
+                    return Ok(v);
+                }
+                promoted!(self, ctx, left, *?, right)
             }
             BinaryBuiltin::Div => {
-                make_ops!(Int, i, j, left, right, i / j);
+                check_zero(self, &right)?;
+                check_product(ctx.config(), &left, &right)?;
+                // division of integers is exact, giving a rational
+                if left.to_big_int().is_some() && right.to_big_int().is_some() {
+                    return Value::rational(left, right);
+                }
+                promoted!(self, ctx, left, /?, right)
+            }
+            BinaryBuiltin::IntDiv => {
+                check_zero(self, &right)?;
+                match int_op(&left, &right, i64::checked_div, |i, j| i / j) {
+                    Some(v) => Ok(v),
+                    Option::None => Err(bad_types(self, left, right)),
+                }
+            }
+            BinaryBuiltin::EuclidDiv => {
+                check_zero(self, &right)?;
+                match int_op(&left, &right, i64::checked_div_euclid, div_euclid)
+                {
+                    Some(v) => Ok(v),
+                    Option::None => Err(bad_types(self, left, right)),
+                }
             }
             BinaryBuiltin::Mod => {
-                make_ops!(Int, i, j, left, right, i % j);
+                check_zero(self, &right)?;
+                if let Some(v) =
+                    int_op(&left, &right, i64::checked_rem_euclid, rem_euclid)
+                {
+                    return Ok(v);
+                }
+                // floats are binary fractions, so their remainder is exact
+                match promote(ctx, left, right) {
+                    (Rational(i), Rational(j)) => {
+                        Ok(Value::from(rem_euclid(i, j)))
+                    }
+                    (Float(i), Float(j)) => {
+                        let prec = i.prec().max(j.prec());
+                        let r = rem_euclid(i.to_big_rat(), j.to_big_rat());
+                        Ok(Value::from(BigFloat::from_big_rat(&r, prec)))
+                    }
+                    (l, r) => Err(bad_types(self, l, r)),
+                }
             }
             BinaryBuiltin::Exp => {
-                if let Int(i) = left {
-                    if let Int(j) = right {
-                        if let Some(res) = i.checked_pow(j.try_into().unwrap())
-                        {
-                            return Int(res);
-                        } else {
-                            return Float(
-                                (i as f64).powi(j.try_into().unwrap()),
-                            );
+                if let Some(v) = int_pow(ctx.config(), &left, &right)? {
+                    return Ok(v);
+                }
+                if left.is_zero() && right.to_f64().is_some_and(|r| r < 0.0) {
+                    errorf!("division by zero in `{self}`");
+                }
+                let prec = ctx.config().float_prec();
+                match (left, right.to_big_int()) {
+                    (Rational(i), Some(j)) => {
+                        let Ok(j) = i32::try_from(j) else {
+                            errorf!("exponent {right} too large");
+                        };
+                        let exp = j.unsigned_abs().into();
+                        let bits = pow_bits(i.numer(), exp)
+                            .max(pow_bits(i.denom(), exp));
+                        ctx.config().check_bits(bits)?;
+                        Ok(Value::from(i.pow(j)))
+                    }
+                    (Float(f), Some(j)) => {
+                        let Some(j) = j.to_i64() else {
+                            errorf!("exponent {right} too large");
+                        };
+                        Ok(Value::from(f.powi(j)?))
+                    }
+                    (Complex(c), Some(j)) if j.to_i32().is_some() => {
+                        let c = c.powi(j.to_i32().unwrap());
+                        Value::from_complex(c, prec)
+                    }
+                    (left, _) if left.is_complex() || right.is_complex() => {
+                        match promote(ctx, left, right) {
+                            (Complex(i), Complex(j)) => {
+                                Value::from_complex(i.powc(j), prec)
+                            }
+                            (l, r) => Err(bad_types(self, l, r)),
                         }
                     }
+                    (left, _) => match float_pow(ctx, &left, &right)? {
+                        Some(v) => Ok(v),
+                        Option::None => Err(bad_types(self, left, right)),
+                    },
+                }
+            }
+            BinaryBuiltin::Eq => Ok(truth(equal(&left, &right))),
+            BinaryBuiltin::Ne => Ok(truth(!equal(&left, &right))),
+            BinaryBuiltin::Lt
+            | BinaryBuiltin::Le
+            | BinaryBuiltin::Gt
+            | BinaryBuiltin::Ge => {
+                let Some(ord) = left.compare(&right) else {
+                    return Err(bad_types(self, left, right));
+                };
+                Ok(truth(match self {
+                    BinaryBuiltin::Lt => ord.is_lt(),
+                    BinaryBuiltin::Le => ord.is_le(),
+                    BinaryBuiltin::Gt => ord.is_gt(),
+                    _ => ord.is_ge(),
+                }))
+            }
+            BinaryBuiltin::BitAnd => {
+                match int_op(&left, &right, |i, j| Some(i & j), |i, j| i & j) {
+                    Some(v) => Ok(v),
+                    Option::None => Err(bad_types(self, left, right)),
+                }
+            }
+            BinaryBuiltin::BitOr => {
+                match int_op(&left, &right, |i, j| Some(i | j), |i, j| i | j) {
+                    Some(v) => Ok(v),
+                    Option::None => Err(bad_types(self, left, right)),
+                }
+            }
+            BinaryBuiltin::BitXor => {
+                match int_op(&left, &right, |i, j| Some(i ^ j), |i, j| i ^ j) {
+                    Some(v) => Ok(v),
+                    Option::None => Err(bad_types(self, left, right)),
+                }
+            }
+            BinaryBuiltin::Shl | BinaryBuiltin::Shr => {
+                let (Some(i), Some(n)) =
+                    (left.to_big_int(), right.to_big_int())
+                else {
+                    return Err(bad_types(self, left, right));
+                };
+                let Some(n) = n.to_usize() else {
+                    errorf!("bad shift count {right}");
+                };
+                if matches!(self, BinaryBuiltin::Shr) {
+                    return Ok(Value::from(i >> n));
+                }
+                ctx.config().check_bits(i.bits() + n as u64)?;
+                Ok(Value::from(i << n))
+            }
+            BinaryBuiltin::And
+            | BinaryBuiltin::Or
+            | BinaryBuiltin::Xor
+            | BinaryBuiltin::Nand
+            | BinaryBuiltin::Nor => {
+                let (Some(l), Some(r)) = (boolean(&left), boolean(&right))
+                else {
+                    return Err(bad_types(self, left, right));
+                };
+                Ok(truth(match self {
+                    BinaryBuiltin::And => l && r,
+                    BinaryBuiltin::Or => l || r,
+                    BinaryBuiltin::Xor => l != r,
+                    BinaryBuiltin::Nand => !(l && r),
+                    _ => !(l || r),
+                }))
+            }
+            BinaryBuiltin::Min | BinaryBuiltin::Max => {
+                let Some(ord) = left.compare(&right) else {
+                    return Err(bad_types(self, left, right));
+                };
+                let keep = match self {
+                    BinaryBuiltin::Min => ord.is_le(),
+                    _ => ord.is_ge(),
+                };
+                Ok(if keep { left } else { right })
+            }
+            BinaryBuiltin::Log => {
+                if left.is_zero() || right.is_zero() {
+                    errorf!("log of zero");
+                }
+                if equal(&left, &Int(1)) {
+                    errorf!("log: base 1");
+                }
+                let prec = ctx.config().float_prec();
+                let (b, x) =
+                    (left.to_big_float(prec), right.to_big_float(prec));
+                if let (Some(b), Some(x)) = (b, x) {
+                    if !b.is_negative() && !x.is_negative() {
+                        return Ok(Value::from((&x.ln()? / &b.ln()?)?));
+                    }
+                }
+                // a negative operand has a complex logarithm
+                match (to_complex(&left), to_complex(&right)) {
+                    (Some(b), Some(x)) => {
+                        Value::from_complex(x.ln() / b.ln(), prec)
+                    }
+                    _ => Err(bad_types(self, left, right)),
+                }
+            }
+            BinaryBuiltin::Binomial => {
+                let (Some(k), Some(n)) =
+                    (left.to_big_int(), right.to_big_int())
+                else {
+                    return Err(bad_types(self, left, right));
+                };
+                Ok(Value::from(binomial(ctx.config(), k, n)?))
+            }
+            BinaryBuiltin::IntMod => {
+                check_zero(self, &right)?;
+                match int_op(&left, &right, i64::checked_rem, |i, j| i % j) {
+                    Some(v) => Ok(v),
+                    Option::None => Err(bad_types(self, left, right)),
                 }
-                make_ops!(
-                    Rational,
-                    i,
-                    Int,
-                    j,
-                    left,
-                    right,
-                    Rational,
-                    i.pow(j.try_into().unwrap())
-                );
             }
+            _ => unreachable!("{self:?} is not elementwise"),
         }
-        todo!()
+    }
+}
+
+/// evaluates `left op right` in a new context with the settings in `conf`
+#[cfg(test)]
+fn eval_in(
+    conf: &Config,
+    op: &BinaryBuiltin,
+    left: Value,
+    right: Value,
+) -> Result<Value, Error> {
+    op.eval_binary(&mut Context::new(conf), left, right)
+}
+
+/// evaluates `left op right` with the default settings
+#[cfg(test)]
+fn eval(op: &BinaryBuiltin, left: Value, right: Value) -> Result<Value, Error> {
+    eval_in(&Config::default(), op, left, right)
+}
+
+#[test]
+fn structural() {
+    use super::ints;
+    // take pads with zeros, from the front for a negative count
+    let taken = eval(&BinaryBuiltin::Take, Value::Int(-4), ints(&[1, 2]));
+    assert_eq!(taken, Ok(ints(&[0, 0, 1, 2])));
+    let m = matrix::shaped(vec![2, 2], ints(&[1, 2, 3, 4]).into_elems());
+    let joined = eval(&BinaryBuiltin::Catenate, m, ints(&[5, 6]));
+    let want =
+        matrix::shaped(vec![2, 3], ints(&[1, 2, 5, 3, 4, 6]).into_elems());
+    assert_eq!(joined, Ok(want));
+    let digits = eval(
+        &BinaryBuiltin::Encode,
+        ints(&[24, 60, 60]),
+        Value::Int(3723),
+    );
+    assert_eq!(digits, Ok(ints(&[1, 2, 3])));
+    let time = eval(
+        &BinaryBuiltin::Decode,
+        ints(&[24, 60, 60]),
+        ints(&[1, 2, 3]),
+    );
+    assert_eq!(time, Ok(Value::Int(3723)));
+    let found = eval(&BinaryBuiltin::IndexOf, ints(&[5, 6, 7]), ints(&[7, 8]));
+    assert_eq!(found, Ok(ints(&[3, 0])));
+}
+
+#[test]
+fn promotion() {
+    use num::BigRational;
+    let conf = Config::default();
+    let third = BigRational::new(1.into(), 3.into());
+    let tenth = BigFloat::from_f64(0.1, conf.float_prec()).unwrap();
+    let values = [
+        Value::Int(2),
+        Value::BigInt(BigInt::one() << 70),
+        Value::Rational(third.clone()),
+        Value::Float(tenth),
+        Value::Complex(num::Complex::new(1.0, 1.0)),
+    ];
+    // the sum has the type of the higher operand, in either order
+    for (i, l) in values.iter().enumerate() {
+        for (j, r) in values.iter().enumerate() {
+            let sum = eval(&BinaryBuiltin::Plus, l.clone(), r.clone()).unwrap();
+            let want = values[i.max(j)].type_name();
+            assert_eq!(sum.type_name(), want, "{l} + {r}");
+        }
+    }
+    // results shrink back to the lowest type that holds them
+    let half = Value::Rational(BigRational::new(1.into(), 2.into()));
+    let one = eval(&BinaryBuiltin::Plus, half.clone(), half);
+    assert_eq!(one, Ok(Value::Int(1)));
+    let big = values[1].clone();
+    let zero = eval(&BinaryBuiltin::Minus, big.clone(), big);
+    assert_eq!(zero, Ok(Value::Int(0)));
+    let third = Value::Rational(third);
+    let int = eval(&BinaryBuiltin::Mul, third, Value::Int(3));
+    assert_eq!(int, Ok(Value::Int(1)));
+    let i = Value::Complex(num::Complex::new(0.0, 1.0));
+    let real = eval(&BinaryBuiltin::Mul, i.clone(), i);
+    assert_eq!(real, Ok(Value::Int(-1)));
+    let err = eval(&BinaryBuiltin::Lt, Value::Char('a'), Value::Int(1));
+    assert_eq!(
+        err.unwrap_err().msg(),
+        "`<` not implemented for char and int"
+    );
+}
+
+#[test]
+fn elementwise() {
+    use super::ints;
+    let mat = |shape: Vec<usize>, v: &[i64]| {
+        matrix::shaped(shape, ints(v).into_elems())
+    };
+    // a scalar is paired with every element, on either side
+    let sum = eval(&BinaryBuiltin::Plus, Value::Int(10), ints(&[1, 2, 3]));
+    assert_eq!(sum, Ok(ints(&[11, 12, 13])));
+    let diff = eval(&BinaryBuiltin::Minus, ints(&[1, 2, 3]), Value::Int(1));
+    assert_eq!(diff, Ok(ints(&[0, 1, 2])));
+    let m = mat(vec![2, 2], &[1, 2, 3, 4]);
+    let twice = eval(&BinaryBuiltin::Mul, m.clone(), Value::Int(2));
+    assert_eq!(twice, Ok(mat(vec![2, 2], &[2, 4, 6, 8])));
+    // a vector is paired with each row of a matrix
+    let rows = eval(&BinaryBuiltin::Plus, ints(&[10, 20]), m.clone());
+    assert_eq!(rows, Ok(mat(vec![2, 2], &[11, 22, 13, 24])));
+    let err = |v: Result<Value, Error>| v.unwrap_err().msg().to_owned();
+    let short = eval(&BinaryBuiltin::Plus, ints(&[1, 2]), ints(&[1, 2, 3]));
+    assert_eq!(err(short), "`+`: shape mismatch: 2 and 3");
+    let wide = mat(vec![2, 3], &[1, 2, 3, 4, 5, 6]);
+    let mats = eval(&BinaryBuiltin::Mul, m.clone(), wide);
+    assert_eq!(err(mats), "`*`: shape mismatch: 2 2 and 2 3");
+    let long = eval(&BinaryBuiltin::Minus, m, ints(&[1, 2, 3]));
+    assert_eq!(err(long), "`-`: shape mismatch: 2 2 and 3");
+}
+
+#[test]
+fn bounded_powers() {
+    let mut conf = Config::default();
+    let pow = |conf: &Config, l: Value, r: i64| {
+        eval_in(conf, &BinaryBuiltin::Exp, l, Value::Int(r))
+    };
+    let third = Value::Rational(num::BigRational::new(1.into(), 3.into()));
+    // the size is estimated before the power is computed
+    assert!(pow(&conf, Value::Int(10), 1_000_000_000).is_err());
+    assert!(pow(&conf, Value::Int(3), 2_000_000_000).is_err());
+    assert!(pow(&conf, third.clone(), 2_000_000_000).is_err());
+    assert_eq!(pow(&conf, Value::Int(1), 1_000_000_000), Ok(Value::Int(1)));
+    assert_eq!(pow(&conf, Value::Int(-1), 999_999_999), Ok(Value::Int(-1)));
+    conf.set_max_bits(64);
+    assert!(pow(&conf, Value::Int(2), 1000).is_err());
+    assert!(pow(&conf, third.clone(), 100).is_err());
+    assert!(pow(&conf, Value::Int(3), 40).is_ok());
+    conf.set_max_bits(0);
+    assert!(pow(&conf, third, 100).is_ok());
+}
+
+#[test]
+fn bounded_arithmetic() {
+    let mut conf = Config::default();
+    conf.set_max_bits(64);
+    let big = Value::from(BigInt::one() << 40);
+    assert!(
+        eval_in(&conf, &BinaryBuiltin::Mul, big.clone(), big.clone()).is_err()
+    );
+    assert!(eval_in(
+        &conf,
+        &BinaryBuiltin::Shl,
+        Value::Int(1),
+        Value::Int(100)
+    )
+    .is_err());
+    let tiny = eval_in(&conf, &BinaryBuiltin::Div, Value::Int(1), big.clone())
+        .unwrap();
+    let third = Value::Rational(num::BigRational::new(1.into(), 3.into()));
+    assert!(eval_in(&conf, &BinaryBuiltin::Plus, tiny, third).is_ok());
+    let tinier =
+        eval_in(&conf, &BinaryBuiltin::Div, Value::Int(1), big).unwrap();
+    assert!(
+        eval_in(&conf, &BinaryBuiltin::Mul, tinier.clone(), tinier).is_err()
+    );
+    assert_eq!(
+        eval_in(&conf, &BinaryBuiltin::Mul, Value::Int(3), Value::Int(4)),
+        Ok(Value::Int(12))
+    );
+    let (k, n) = (Value::Int(1_000_000), Value::Int(2_000_000));
+    assert!(eval_in(&conf, &BinaryBuiltin::Binomial, k, n).is_err());
+    let (k, n) = (Value::Int(30), Value::Int(60));
+    assert_eq!(
+        eval_in(&conf, &BinaryBuiltin::Binomial, k, n),
+        Ok(Value::Int(118264581564861424))
+    );
+}
+
+#[test]
+fn division() {
+    use num::BigRational;
+    let div = |op: &BinaryBuiltin, l: i64, r: i64| {
+        eval(op, Value::Int(l), Value::Int(r))
+    };
+    // division is exact, and whole quotients come back as integers
+    let half = Value::Rational(BigRational::new((-7).into(), 2.into()));
+    assert_eq!(div(&BinaryBuiltin::Div, -7, 2), Ok(half));
+    assert_eq!(div(&BinaryBuiltin::Div, 6, 3), Ok(Value::Int(2)));
+    // div and mod are Euclidean, so the remainder is never negative; idiv
+    // and imod truncate toward zero
+    let cases = [
+        (BinaryBuiltin::EuclidDiv, [-4, -3, 4, 3]),
+        (BinaryBuiltin::Mod, [1, 1, 1, 1]),
+        (BinaryBuiltin::IntDiv, [-3, -3, 3, 3]),
+        (BinaryBuiltin::IntMod, [-1, 1, -1, 1]),
+    ];
+    let operands = [(-7, 2), (7, -2), (-7, -2), (7, 2)];
+    for (op, want) in cases {
+        for ((l, r), want) in operands.into_iter().zip(want) {
+            assert_eq!(div(&op, l, r), Ok(Value::Int(want)), "{l} {op} {r}");
+        }
+    }
+    for op in [
+        BinaryBuiltin::Div,
+        BinaryBuiltin::EuclidDiv,
+        BinaryBuiltin::Mod,
+        BinaryBuiltin::IntDiv,
+        BinaryBuiltin::IntMod,
+    ] {
+        let err = div(&op, 1, 0).unwrap_err();
+        assert!(err.msg().contains("division by zero"), "{op}: {err}");
     }
 }
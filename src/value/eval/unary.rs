@@ -1,21 +1,73 @@
-use num::integer::Roots;
+use num::{Complex, Signed, ToPrimitive};
+use rand::Rng;
 
-use super::super::Value;
+use super::super::{
+    bigfloat::BigFloat,
+    format,
+    matrix::{self, shape_string},
+    Value,
+};
 
-use crate::exec::context::Context;
+use crate::{
+    error::{errorf, Error},
+    exec::context::Context,
+};
 
-use super::super::context::UnaryOp;
+use super::super::context::{BinaryOp, UnaryOp};
 
-use super::ParseBuiltinError;
+use super::{binary::BinaryBuiltin, string_of, ParseBuiltinError};
 
-use std::str::FromStr;
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
 
+#[derive(Debug)]
 pub enum UnaryBuiltin {
     Roll,
+    Factorial,
+    Plus,
+    Negate,
+    Recip,
+    BitNot,
+    Not,
+    Abs,
+    Sgn,
+    Ceil,
+    Floor,
     Sqrt,
+    Exp,
+    Log,
+    Sin,
+    Cos,
+    Tan,
+    Asin,
     Acos,
+    Atan,
+    Sinh,
+    Cosh,
+    Tanh,
+    Asinh,
+    Acosh,
+    Atanh,
+    Real,
+    Imag,
+    Phase,
+    Float,
     Char,
     Code,
+    Iota,
+    Rho,
+    Ravel,
+    Rev,
+    Flip,
+    Transp,
+    Up,
+    Down,
+    Ivy,
+    Text,
+    First,
+    Count,
+    Box,
+    Unbox,
+    Inv,
 }
 
 /// return whether or not `s` is a UnaryBuiltin
@@ -23,66 +75,776 @@ pub fn is_unary_op(s: &str) -> bool {
     UnaryBuiltin::from_str(s).is_ok()
 }
 
+/// the names of the unary builtins that are words rather than symbols
+pub const WORDS: &[&str] = &[
+    "not", "abs", "sgn", "ceil", "floor", "sqrt", "exp", "log", "sin", "cos",
+    "tan", "asin", "acos", "atan", "sinh", "cosh", "tanh", "asinh", "acosh",
+    "atanh", "real", "imag", "phase", "float", "char", "code", "iota", "rho",
+    "ravel", "rev", "rot", "flip", "transp", "up", "down", "ivy", "text",
+    "first", "count", "box", "unbox", "inv",
+];
+
 impl FromStr for UnaryBuiltin {
     type Err = ParseBuiltinError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "?" => Ok(Self::Roll),
+            "!" => Ok(Self::Factorial),
+            "+" => Ok(Self::Plus),
+            "-" => Ok(Self::Negate),
+            "/" => Ok(Self::Recip),
+            "^" => Ok(Self::BitNot),
+            "not" => Ok(Self::Not),
+            "abs" => Ok(Self::Abs),
+            "sgn" => Ok(Self::Sgn),
+            "ceil" => Ok(Self::Ceil),
+            "floor" => Ok(Self::Floor),
             "sqrt" => Ok(Self::Sqrt),
+            "**" | "exp" => Ok(Self::Exp),
+            "log" => Ok(Self::Log),
+            "sin" => Ok(Self::Sin),
+            "cos" => Ok(Self::Cos),
+            "tan" => Ok(Self::Tan),
+            "asin" => Ok(Self::Asin),
             "acos" => Ok(Self::Acos),
+            "atan" => Ok(Self::Atan),
+            "sinh" => Ok(Self::Sinh),
+            "cosh" => Ok(Self::Cosh),
+            "tanh" => Ok(Self::Tanh),
+            "asinh" => Ok(Self::Asinh),
+            "acosh" => Ok(Self::Acosh),
+            "atanh" => Ok(Self::Atanh),
+            "real" => Ok(Self::Real),
+            "imag" => Ok(Self::Imag),
+            "phase" => Ok(Self::Phase),
+            "float" => Ok(Self::Float),
             "char" => Ok(Self::Char),
             "code" => Ok(Self::Code),
+            "iota" => Ok(Self::Iota),
+            "rho" => Ok(Self::Rho),
+            "," | "ravel" => Ok(Self::Ravel),
+            "rev" | "rot" => Ok(Self::Rev),
+            "flip" => Ok(Self::Flip),
+            "transp" => Ok(Self::Transp),
+            "up" => Ok(Self::Up),
+            "down" => Ok(Self::Down),
+            "ivy" => Ok(Self::Ivy),
+            "text" => Ok(Self::Text),
+            "first" => Ok(Self::First),
+            "count" => Ok(Self::Count),
+            "box" => Ok(Self::Box),
+            "unbox" => Ok(Self::Unbox),
+            "inv" => Ok(Self::Inv),
             _ => Err(ParseBuiltinError),
         }
     }
 }
 
+/// displays the operator as it is written in ivy
+impl Display for UnaryBuiltin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use UnaryBuiltin::*;
+        let name = match self {
+            Roll => "?",
+            Factorial => "!",
+            Plus => "+",
+            Negate => "-",
+            Recip => "/",
+            BitNot => "^",
+            Not => "not",
+            Abs => "abs",
+            Sgn => "sgn",
+            Ceil => "ceil",
+            Floor => "floor",
+            Sqrt => "sqrt",
+            Exp => "**",
+            Log => "log",
+            Sin => "sin",
+            Cos => "cos",
+            Tan => "tan",
+            Asin => "asin",
+            Acos => "acos",
+            Atan => "atan",
+            Sinh => "sinh",
+            Cosh => "cosh",
+            Tanh => "tanh",
+            Asinh => "asinh",
+            Acosh => "acosh",
+            Atanh => "atanh",
+            Real => "real",
+            Imag => "imag",
+            Phase => "phase",
+            Float => "float",
+            Char => "char",
+            Code => "code",
+            Iota => "iota",
+            Rho => "rho",
+            Ravel => ",",
+            Rev => "rev",
+            Flip => "flip",
+            Transp => "transp",
+            Up => "up",
+            Down => "down",
+            Ivy => "ivy",
+            Text => "text",
+            First => "first",
+            Count => "count",
+            Box => "box",
+            Unbox => "unbox",
+            Inv => "inv",
+        };
+        f.write_str(name)
+    }
+}
+
+/// returns the error reporting that `op` cannot be applied to a value of this
+/// type
+fn bad_type(op: &UnaryBuiltin, right: &Value) -> Error {
+    Error::eval(format!("`{op}` not implemented for {}", right.type_name()))
+}
+
+/// returns the magnitude of a number as an f64, for choosing pivots
+fn magnitude(v: &Value) -> f64 {
+    match v {
+        Value::Complex(c) => c.norm(),
+        v => v.to_f64().map_or(0.0, f64::abs),
+    }
+}
+
+impl UnaryBuiltin {
+    /// reports whether the operator applies to each element of its operand
+    /// separately, rather than to its shape as a whole
+    fn is_elementwise(&self) -> bool {
+        use UnaryBuiltin::*;
+        !matches!(
+            self,
+            Iota | Rho
+                | Ravel
+                | Rev
+                | Flip
+                | Transp
+                | Up
+                | Down
+                | Ivy
+                | Text
+                | First
+                | Count
+                | Box
+                | Unbox
+        )
+    }
+
+    /// applies `real` to a real operand converted to a float, or `complex` to
+    /// a complex one
+    fn float_fn(
+        &self,
+        ctx: &Context,
+        right: Value,
+        real: impl FnOnce(BigFloat) -> Result<Value, Error>,
+        complex: fn(Complex<f64>) -> Complex<f64>,
+    ) -> Result<Value, Error> {
+        let prec = ctx.config().float_prec();
+        if let Value::Complex(c) = right {
+            return Value::from_complex(complex(c), prec);
+        }
+        match right.to_big_float(prec) {
+            Some(f) => real(f),
+            Option::None => Err(bad_type(self, &right)),
+        }
+    }
+
+    /// applies the operator to the operand as a whole
+    fn eval_shape(
+        &self,
+        ctx: &mut Context,
+        right: Value,
+    ) -> Result<Value, Error> {
+        use Value::*;
+        let origin = ctx.config().origin();
+        match self {
+            UnaryBuiltin::Iota => match right {
+                Int(n) if n >= 0 => {
+                    ctx.config().check_len(n as usize)?;
+                    Ok(Vector((0..n).map(|i| Int(i + origin as i64)).collect()))
+                }
+                _ => errorf!("iota: bad argument {right}"),
+            },
+            UnaryBuiltin::Rho => Ok(Vector(
+                right.shape().into_iter().map(|d| Int(d as i64)).collect(),
+            )),
+            UnaryBuiltin::Ravel => Ok(Vector(right.into_elems())),
+            UnaryBuiltin::Rev => match right {
+                Vector(mut v) => {
+                    v.reverse();
+                    Ok(Vector(v))
+                }
+                Matrix(m) if m.rank() > 0 => {
+                    let shape = m.shape().to_vec();
+                    let cols = shape[shape.len() - 1];
+                    let mut data = m.into_data();
+                    if cols > 0 {
+                        data.chunks_mut(cols).for_each(<[Value]>::reverse);
+                    }
+                    Ok(matrix::shaped(shape, data))
+                }
+                v => Ok(v),
+            },
+            UnaryBuiltin::Flip => match right {
+                Vector(mut v) => {
+                    v.reverse();
+                    Ok(Vector(v))
+                }
+                Matrix(m) if m.rank() > 0 && !m.data().is_empty() => {
+                    let shape = m.shape().to_vec();
+                    let cell = m.data().len() / shape[0];
+                    let data = m.data().chunks(cell).rev().flatten().cloned();
+                    Ok(matrix::shaped(shape, data.collect()))
+                }
+                v => Ok(v),
+            },
+            UnaryBuiltin::Transp => match right {
+                Matrix(m) => Ok(Matrix(m.transpose())),
+                v => Ok(v),
+            },
+            UnaryBuiltin::Up => self.grade(right, false, origin),
+            UnaryBuiltin::Down => self.grade(right, true, origin),
+            UnaryBuiltin::Ivy => match string_of(&right) {
+                Some(text) => crate::run::eval_text(ctx, &text),
+                Option::None => errorf!("ivy: argument must be a string"),
+            },
+            UnaryBuiltin::Text => {
+                let text = format::sprint(&right, ctx.config());
+                Ok(Vector(text.chars().map(Char).collect()))
+            }
+            UnaryBuiltin::First => match right {
+                Vector(v) if !v.is_empty() => Ok(v.into_iter().next().unwrap()),
+                Matrix(m) if !m.data().is_empty() => {
+                    Ok(m.into_data().into_iter().next().unwrap())
+                }
+                v => Ok(v),
+            },
+            UnaryBuiltin::Count => {
+                Ok(Int(right.shape().first().map_or(1, |&n| n as i64)))
+            }
+            UnaryBuiltin::Box => match right {
+                v @ (Vector(_) | Matrix(_)) => Ok(Vector(vec![v])),
+                v => Ok(v),
+            },
+            UnaryBuiltin::Unbox => match right {
+                Vector(v) if v.len() == 1 => Ok(v.into_iter().next().unwrap()),
+                Matrix(m) if m.data().len() == 1 => {
+                    Ok(m.into_data().into_iter().next().unwrap())
+                }
+                v => Ok(v),
+            },
+            _ => unreachable!("{self:?} is elementwise"),
+        }
+    }
+
+    /// returns the indices, counted from `origin`, that sort the items along
+    /// the first axis of `v`, into ascending order or, for `down`, descending
+    /// order. Equal items keep their order. Matrix rows compare element by
+    /// element.
+    fn grade(
+        &self,
+        v: Value,
+        descending: bool,
+        origin: usize,
+    ) -> Result<Value, Error> {
+        let n = v.shape().first().copied().unwrap_or(1);
+        let data = v.into_elems();
+        let cell = data.len().checked_div(n).unwrap_or(0);
+        let mut bad = Option::None;
+        let mut idx: Vec<usize> = (0..n).collect();
+        idx.sort_by(|&i, &j| {
+            let (a, b) = (&data[i * cell..][..cell], &data[j * cell..][..cell]);
+            let mut ord = Ordering::Equal;
+            for (x, y) in a.iter().zip(b) {
+                match x.compare(y) {
+                    Some(Ordering::Equal) => continue,
+                    Some(o) => ord = o,
+                    Option::None => {
+                        bad.get_or_insert_with(|| (x.clone(), y.clone()));
+                    }
+                }
+                break;
+            }
+            if descending {
+                ord.reverse()
+            } else {
+                ord
+            }
+        });
+        if let Some((x, y)) = bad {
+            errorf!("`{self}`: cannot compare {x} and {y}");
+        }
+        Ok(Value::Vector(
+            idx.into_iter()
+                .map(|i| Value::Int((i + origin) as i64))
+                .collect(),
+        ))
+    }
+
+    /// evaluates sinh, cosh or tanh of the real `f` from e**f
+    fn hyperbolic(&self, f: BigFloat) -> Result<Value, Error> {
+        let prec = f.prec();
+        let wp = prec + 64;
+        let one = BigFloat::one(wp);
+        let ex = f.with_prec(wp).exp()?;
+        let inv = (&one / &ex)?;
+        let two = BigFloat::from_big_int(2.into(), wp);
+        let r = match self {
+            UnaryBuiltin::Sinh => (&(&ex - &inv) / &two)?,
+            UnaryBuiltin::Cosh => (&(&ex + &inv) / &two)?,
+            _ => (&(&ex - &inv) / &(&ex + &inv))?,
+        };
+        Ok(r.with_prec(prec).into())
+    }
+}
+
+/// returns the inverse of the square matrix `m` by Gauss-Jordan elimination.
+/// The arithmetic is done by the builtin operators, so integer and rational
+/// matrices have exact inverses.
+fn inverse(ctx: &mut Context, m: matrix::Matrix) -> Result<Value, Error> {
+    let &[n, cols] = m.shape() else {
+        errorf!(
+            "inv: matrix of shape {} is not square",
+            shape_string(m.shape())
+        );
+    };
+    if n != cols {
+        errorf!("inv: matrix of shape {n} {cols} is not square");
+    }
+    let (sub, mul, div) =
+        (BinaryBuiltin::Minus, BinaryBuiltin::Mul, BinaryBuiltin::Div);
+    // each row is extended with the corresponding row of the identity
+    let data = m.into_data();
+    let mut rows: Vec<Vec<Value>> = (0..n)
+        .map(|i| {
+            let mut row = data[i * n..][..n].to_vec();
+            row.extend((0..n).map(|j| Value::Int((i == j).into())));
+            row
+        })
+        .collect();
+    for col in 0..n {
+        // the largest pivot keeps floating-point error down
+        let Some(pivot) =
+            (col..n)
+                .filter(|&r| !rows[r][col].is_zero())
+                .max_by(|&a, &b| {
+                    magnitude(&rows[a][col])
+                        .total_cmp(&magnitude(&rows[b][col]))
+                })
+        else {
+            errorf!("inv: matrix is singular");
+        };
+        rows.swap(col, pivot);
+        let p = rows[col][col].clone();
+        for v in rows[col].iter_mut() {
+            *v = div.eval_binary(ctx, std::mem::take(v), p.clone())?;
+        }
+        let pivot_row = rows[col].clone();
+        for (r, row) in rows.iter_mut().enumerate() {
+            if r == col || row[col].is_zero() {
+                continue;
+            }
+            let f = row[col].clone();
+            for (v, p) in row.iter_mut().zip(&pivot_row) {
+                let d = mul.eval_binary(ctx, f.clone(), p.clone())?;
+                *v = sub.eval_binary(ctx, std::mem::take(v), d)?;
+            }
+        }
+    }
+    let data = rows.into_iter().flat_map(|r| r.into_iter().skip(n));
+    Ok(Value::Matrix(matrix::Matrix::new(
+        vec![n, n],
+        data.collect(),
+    )))
+}
+
 impl<'a> UnaryOp<'a> for UnaryBuiltin {
-    fn eval_unary(&self, _ctx: &Context<'a>, right: Value) -> Value {
+    fn eval_unary(
+        &self,
+        ctx: &mut Context<'a>,
+        right: Value,
+    ) -> Result<Value, Error> {
         use Value::*;
+        if !self.is_elementwise() {
+            return self.eval_shape(ctx, right);
+        }
+        match right {
+            Matrix(m) if matches!(self, UnaryBuiltin::Inv) => {
+                return inverse(ctx, m)
+            }
+            Vector(v) => {
+                return Ok(Vector(
+                    v.into_iter()
+                        .map(|e| self.eval_unary(ctx, e))
+                        .collect::<Result<_, _>>()?,
+                ))
+            }
+            Matrix(m) => {
+                return Ok(Matrix(m.map(|e| self.eval_unary(ctx, e))?))
+            }
+            _ => {}
+        }
+        let prec = ctx.config().float_prec();
         match self {
-            UnaryBuiltin::Sqrt => match right {
-                Float(f) => Value::Float(f.sqrt()),
-                Int(f) => {
-                    if f >= 0 {
-                        Value::Int(f.sqrt())
-                    } else {
-                        Value::complex(Int(0), Int(f.abs().sqrt()))
+            UnaryBuiltin::Roll => match right {
+                Int(n) if n > 0 => {
+                    let origin = ctx.config().origin() as i64;
+                    Ok(Int(ctx.rng().gen_range(0..n) + origin))
+                }
+                v => errorf!("roll: bad argument {v}"),
+            },
+            UnaryBuiltin::Factorial => {
+                let Some(n) = right.to_big_int() else {
+                    return Err(bad_type(self, &right));
+                };
+                let Some(n) = n.to_u64() else {
+                    errorf!("!: bad argument {right}");
+                };
+                // n! has fewer than n log2 n bits
+                let max = ctx.config().max_bits();
+                if max > 0 && n as f64 * (n as f64).log2() > max as f64 {
+                    errorf!("result of !{n} exceeds {max} bits");
+                }
+                Ok(Value::from((2..=n).product::<num::BigInt>()))
+            }
+            UnaryBuiltin::Plus => match right {
+                Complex(c) => Ok(Complex(c.conj())),
+                Char(_) | None => Err(bad_type(self, &right)),
+                v => Ok(v),
+            },
+            UnaryBuiltin::Negate => match right {
+                Int(i) => match i.checked_neg() {
+                    Some(i) => Ok(Int(i)),
+                    Option::None => Ok(Value::from(-num::BigInt::from(i))),
+                },
+                BigInt(b) => Ok(Value::from(-b)),
+                Rational(r) => Ok(Value::from(-r)),
+                Float(f) => Ok(Float(-f)),
+                Complex(c) => Ok(Complex(-c)),
+                v => Err(bad_type(self, &v)),
+            },
+            UnaryBuiltin::Recip | UnaryBuiltin::Inv => {
+                if right.is_zero() {
+                    errorf!("division by zero in `{self}`");
+                }
+                match right {
+                    Float(f) => {
+                        Ok(Value::from((&BigFloat::one(f.prec()) / &f)?))
                     }
+                    Complex(c) => Value::from_complex(c.inv(), prec),
+                    v => Value::rational(Int(1), v),
                 }
-                Complex(_) => todo!(),
-                Rational(_) => todo!(),
-                None => todo!(),
-                Char(_) => todo!(),
+            }
+            UnaryBuiltin::BitNot => match right {
+                Int(i) => Ok(Int(!i)),
+                BigInt(b) => Ok(Value::from(-b - 1)),
+                v => Err(bad_type(self, &v)),
+            },
+            UnaryBuiltin::Not => match right {
+                Char(_) | None => Err(bad_type(self, &right)),
+                v => Ok(Int(v.is_zero().into())),
             },
-            UnaryBuiltin::Roll => todo!(),
-            UnaryBuiltin::Acos => match right {
-                Float(_) => todo!(),
-                Int(_) => todo!(),
-                Complex(c) => Value::Complex(c.acos()),
-                Rational(_) => todo!(),
-                None => todo!(),
-                Char(_) => todo!(),
+            UnaryBuiltin::Abs => match right {
+                Int(i) => match i.checked_abs() {
+                    Some(i) => Ok(Int(i)),
+                    Option::None => Ok(Value::from(num::BigInt::from(i).abs())),
+                },
+                BigInt(b) => Ok(Value::from(b.abs())),
+                Rational(r) => Ok(Value::from(r.abs())),
+                Float(f) => Ok(Float(f.abs())),
+                Complex(c) => Ok(BigFloat::from_f64(c.norm(), prec)?.into()),
+                v => Err(bad_type(self, &v)),
+            },
+            UnaryBuiltin::Sgn => match right {
+                Complex(c) => Value::from_complex(c / c.norm(), prec),
+                Char(_) | None => Err(bad_type(self, &right)),
+                v => match v.compare(&Int(0)) {
+                    Some(o) => Ok(Int(o as i64)),
+                    Option::None => Err(bad_type(self, &v)),
+                },
+            },
+            UnaryBuiltin::Floor | UnaryBuiltin::Ceil => {
+                let ceil = matches!(self, UnaryBuiltin::Ceil);
+                match right {
+                    Int(_) | BigInt(_) => Ok(right),
+                    Rational(r) if ceil => Ok(Value::from(r.ceil())),
+                    Rational(r) => Ok(Value::from(r.floor())),
+                    Float(f) if ceil => Ok(Value::from(-(-f).floor())),
+                    Float(f) => Ok(Value::from(f.floor())),
+                    Complex(c) if ceil => {
+                        let c = num::Complex::new(c.re.ceil(), c.im.ceil());
+                        Value::from_complex(c, prec)
+                    }
+                    Complex(c) => {
+                        let c = num::Complex::new(c.re.floor(), c.im.floor());
+                        Value::from_complex(c, prec)
+                    }
+                    v => Err(bad_type(self, &v)),
+                }
+            }
+            UnaryBuiltin::Sqrt => self.float_fn(
+                ctx,
+                right,
+                |f| {
+                    if f.is_negative() {
+                        Value::complex(Int(0), f.abs().sqrt()?.into())
+                    } else {
+                        Ok(f.sqrt()?.into())
+                    }
+                },
+                num::Complex::sqrt,
+            ),
+            UnaryBuiltin::Exp => self.float_fn(
+                ctx,
+                right,
+                |f| Ok(f.exp()?.into()),
+                num::Complex::exp,
+            ),
+            UnaryBuiltin::Log => self.float_fn(
+                ctx,
+                right,
+                |f| {
+                    if f.is_zero() {
+                        errorf!("log of zero");
+                    }
+                    if f.is_negative() {
+                        let c = num::Complex::new(f.to_f64(), 0.0).ln();
+                        return Value::from_complex(c, prec);
+                    }
+                    Ok(f.ln()?.into())
+                },
+                num::Complex::ln,
+            ),
+            UnaryBuiltin::Sin => self.float_fn(
+                ctx,
+                right,
+                |f| Ok(f.sin()?.into()),
+                num::Complex::sin,
+            ),
+            UnaryBuiltin::Cos => self.float_fn(
+                ctx,
+                right,
+                |f| Ok(f.cos()?.into()),
+                num::Complex::cos,
+            ),
+            UnaryBuiltin::Tan => self.float_fn(
+                ctx,
+                right,
+                |f| Ok(f.tan()?.into()),
+                num::Complex::tan,
+            ),
+            UnaryBuiltin::Asin | UnaryBuiltin::Acos => {
+                type Real = fn(&BigFloat) -> Result<BigFloat, Error>;
+                let (real, complex): (Real, _) =
+                    if matches!(self, UnaryBuiltin::Asin) {
+                        (BigFloat::asin, num::Complex::asin as fn(_) -> _)
+                    } else {
+                        (BigFloat::acos, num::Complex::acos as fn(_) -> _)
+                    };
+                self.float_fn(
+                    ctx,
+                    right,
+                    |f| {
+                        if f.abs() > BigFloat::one(prec) {
+                            let c = complex(num::Complex::new(f.to_f64(), 0.0));
+                            return Value::from_complex(c, prec);
+                        }
+                        Ok(real(&f)?.into())
+                    },
+                    complex,
+                )
+            }
+            UnaryBuiltin::Atan => self.float_fn(
+                ctx,
+                right,
+                |f| Ok(f.atan()?.into()),
+                num::Complex::atan,
+            ),
+            UnaryBuiltin::Sinh | UnaryBuiltin::Cosh | UnaryBuiltin::Tanh => {
+                let complex = match self {
+                    UnaryBuiltin::Sinh => num::Complex::sinh,
+                    UnaryBuiltin::Cosh => num::Complex::cosh,
+                    _ => num::Complex::tanh,
+                };
+                self.float_fn(ctx, right, |f| self.hyperbolic(f), complex)
+            }
+            UnaryBuiltin::Asinh => self.float_fn(
+                ctx,
+                right,
+                |f| {
+                    // asinh x = ln(x + sqrt(x**2 + 1)), which is odd
+                    let one = BigFloat::one(prec);
+                    let x = f.abs();
+                    let r = (&x + &(&(&x * &x)? + &one).sqrt()?).ln()?;
+                    Ok(if f.is_negative() { -r } else { r }.into())
+                },
+                num::Complex::asinh,
+            ),
+            UnaryBuiltin::Acosh => self.float_fn(
+                ctx,
+                right,
+                |f| {
+                    // acosh x = ln(x + sqrt(x**2 - 1)) for x >= 1
+                    let one = BigFloat::one(prec);
+                    if f < one {
+                        let c = num::Complex::new(f.to_f64(), 0.0).acosh();
+                        return Value::from_complex(c, prec);
+                    }
+                    Ok((&f + &(&(&f * &f)? - &one).sqrt()?).ln()?.into())
+                },
+                num::Complex::acosh,
+            ),
+            UnaryBuiltin::Atanh => self.float_fn(
+                ctx,
+                right,
+                |f| {
+                    // atanh x = ln((1 + x) / (1 - x)) / 2 for |x| < 1
+                    let one = BigFloat::one(prec);
+                    match f.abs().cmp(&one) {
+                        Ordering::Less => {
+                            let q = (&(&one + &f) / &(&one - &f))?;
+                            let two = BigFloat::from_big_int(2.into(), prec);
+                            Ok((&q.ln()? / &two)?.into())
+                        }
+                        Ordering::Equal => errorf!("atanh of {f} is infinite"),
+                        Ordering::Greater => {
+                            let c = num::Complex::new(f.to_f64(), 0.0).atanh();
+                            Value::from_complex(c, prec)
+                        }
+                    }
+                },
+                num::Complex::atanh,
+            ),
+            UnaryBuiltin::Real | UnaryBuiltin::Imag | UnaryBuiltin::Phase => {
+                match right {
+                    Complex(c) => {
+                        let f = match self {
+                            UnaryBuiltin::Real => c.re,
+                            UnaryBuiltin::Imag => c.im,
+                            _ => c.arg(),
+                        };
+                        Ok(BigFloat::from_f64(f, prec)?.into())
+                    }
+                    Char(_) | None => Err(bad_type(self, &right)),
+                    v => match self {
+                        UnaryBuiltin::Real => Ok(v),
+                        UnaryBuiltin::Phase
+                            if v.compare(&Int(0))
+                                .is_some_and(Ordering::is_lt) =>
+                        {
+                            Ok(Float(BigFloat::pi(prec)))
+                        }
+                        _ => Ok(Int(0)),
+                    },
+                }
+            }
+            UnaryBuiltin::Float => match right {
+                Complex(_) => Ok(right),
+                v => match v.to_big_float(prec) {
+                    Some(f) => Ok(Float(f)),
+                    Option::None => Err(bad_type(self, &v)),
+                },
             },
             UnaryBuiltin::Char => {
                 if let Char(c) = right {
-                    return Char(c);
+                    return Ok(Char(c));
                 }
-
                 if let Int(c) = right {
-                    return Char(
-                        char::from_u32(c.try_into().unwrap()).unwrap(),
-                    );
+                    if let Some(c) =
+                        u32::try_from(c).ok().and_then(char::from_u32)
+                    {
+                        return Ok(Char(c));
+                    }
                 }
-                panic!("char called with {right}");
+                errorf!("char called with {right}");
             }
             UnaryBuiltin::Code => {
                 if let Char(c) = right {
-                    return Int((c as u8).into());
+                    return Ok(Int(u32::from(c).into()));
                 }
-                panic!("code called with {right}");
+                errorf!("code called with {right}");
             }
+            _ => unreachable!("{self:?} applies to the whole operand"),
         }
     }
 }
+
+#[test]
+fn whole_operand() {
+    use super::ints;
+    use crate::config::Config;
+    let conf = Config::default();
+    let mut ctx = Context::new(&conf);
+    let mut eval = |op: &str, v: Value| {
+        UnaryBuiltin::from_str(op).unwrap().eval_unary(&mut ctx, v)
+    };
+    assert_eq!(eval("up", ints(&[3, 1, 4, 1])), Ok(ints(&[2, 4, 1, 3])));
+    assert_eq!(eval("down", ints(&[3, 1, 4, 1])), Ok(ints(&[3, 1, 2, 4])));
+    let m = matrix::shaped(vec![2, 2], ints(&[2, 1, 1, 1]).into_elems());
+    let inv = matrix::shaped(vec![2, 2], ints(&[1, -1, -1, 2]).into_elems());
+    assert_eq!(eval("inv", m.clone()), Ok(inv));
+    let m = matrix::shaped(vec![2, 2], ints(&[1, 2, 3, 4]).into_elems());
+    let t = matrix::shaped(vec![2, 2], ints(&[1, 3, 2, 4]).into_elems());
+    assert_eq!(eval("transp", m), Ok(t));
+    let text = Value::Vector("2 ** 10".chars().map(Value::Char).collect());
+    assert_eq!(eval("ivy", text), Ok(Value::Int(1024)));
+    // a matrix of rank 0 has no axis to reverse
+    let scalar =
+        Value::Matrix(matrix::Matrix::new(vec![], vec![Value::Int(5)]));
+    assert_eq!(eval("rev", scalar.clone()), Ok(scalar.clone()));
+    assert_eq!(eval("flip", scalar.clone()), Ok(scalar));
+    assert!(eval("iota", Value::Int(10_000_000_000)).is_err());
+}
+
+#[test]
+fn seeded_rolls() {
+    use crate::config::Config;
+    let conf = Config::default();
+    let rolls = |ctx: &mut Context| {
+        let sixes = Value::Vector(vec![Value::Int(6); 20]);
+        UnaryBuiltin::Roll.eval_unary(ctx, sixes).unwrap()
+    };
+    // contexts with the same seed roll the same numbers
+    let first = rolls(&mut Context::new(&conf));
+    assert_eq!(first, rolls(&mut Context::new(&conf)));
+    let in_range = |v: &Value| matches!(v, Value::Int(1..=6));
+    assert!(first.into_elems().iter().all(in_range));
+}
+
+#[test]
+fn factorial_and_names() {
+    use super::ints;
+    use crate::config::Config;
+    // each operator displays as a name that parses back to it
+    for name in WORDS
+        .iter()
+        .chain(&["?", "!", "+", "-", "/", "^", "**", ","])
+    {
+        let op = UnaryBuiltin::from_str(name).unwrap();
+        let again = UnaryBuiltin::from_str(&op.to_string()).unwrap();
+        assert_eq!(format!("{op:?}"), format!("{again:?}"));
+    }
+    let conf = Config::default();
+    let mut ctx = Context::new(&conf);
+    let mut eval = |op: &str, v: Value| {
+        UnaryBuiltin::from_str(op).unwrap().eval_unary(&mut ctx, v)
+    };
+    assert_eq!(eval("!", ints(&[0, 1, 5])), Ok(ints(&[1, 1, 120])));
+    assert!(eval("!", Value::Int(-1)).is_err());
+    assert_eq!(eval("rot", ints(&[1, 2, 3])), Ok(ints(&[3, 2, 1])));
+    let err = eval("sqrt", Value::Char('a')).unwrap_err();
+    assert_eq!(err.msg(), "`sqrt` not implemented for char");
+    assert_eq!(
+        eval("/", Value::Int(0)).unwrap_err().msg(),
+        "division by zero in `/`"
+    );
+    // results too large for a complex number are errors, not inf or NaN
+    let huge = num::BigInt::from(10).pow(400);
+    assert!(eval("log", Value::from(-huge.clone())).is_err());
+    assert!(eval("asin", Value::from(huge)).is_err());
+}
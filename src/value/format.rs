@@ -0,0 +1,449 @@
+//! printf-style formatting of values, using the verbs and flags of Go's fmt
+//! package as ivy does: `%d`, `%b`, `%o`, `%x` and `%X` for integers, `%c`
+//! for characters, `%e`, `%f` and `%g` for numbers, and `%s`, `%v` and `%q`
+//! for anything. A format holds a single verb, which is applied to each
+//! element of a vector or matrix in turn.
+
+use num::{BigInt, BigRational, Signed, Zero};
+
+use super::{bigfloat::BigFloat, Value};
+
+use crate::{
+    config::Config,
+    error::{errorf, Error},
+};
+
+/// the largest width or precision a format may have, as in Go
+const MAX_WIDTH: usize = 1_000_000;
+
+/// Spec is a parsed format: the verb with its flags, width and precision, and
+/// the literal text around it
+#[derive(Debug, Default)]
+struct Spec {
+    prefix: String,
+    suffix: String,
+    minus: bool,
+    plus: bool,
+    sharp: bool,
+    space: bool,
+    zero: bool,
+    width: Option<usize>,
+    prec: Option<usize>,
+    verb: char,
+}
+
+/// parses `fmt`, which holds one verb. The leading `%` may be left out.
+fn parse(fmt: &str) -> Result<Spec, Error> {
+    if !fmt.contains('%') {
+        return parse(&format!("%{fmt}"));
+    }
+    let mut spec = Spec::default();
+    let mut chars = fmt.chars().peekable();
+    // the text before the verb, in which %% stands for %
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            spec.prefix.push(c);
+        } else if chars.peek() == Some(&'%') {
+            spec.prefix.push(chars.next().unwrap());
+        } else {
+            break;
+        }
+    }
+    while let Some(&c) = chars.peek() {
+        match c {
+            '-' => spec.minus = true,
+            '+' => spec.plus = true,
+            '#' => spec.sharp = true,
+            ' ' => spec.space = true,
+            '0' => spec.zero = true,
+            _ => break,
+        }
+        chars.next();
+    }
+    let number = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+        let mut n = None;
+        while let Some(d) = chars.peek().and_then(|c| c.to_digit(10)) {
+            let m: usize = n.unwrap_or(0);
+            n = Some(m.saturating_mul(10).saturating_add(d as usize));
+            chars.next();
+        }
+        n
+    };
+    spec.width = number(&mut chars);
+    if chars.peek() == Some(&'.') {
+        chars.next();
+        spec.prec = Some(number(&mut chars).unwrap_or(0));
+    }
+    if spec.width.max(spec.prec).is_some_and(|n| n > MAX_WIDTH) {
+        errorf!("format {fmt:?}: width or precision too large");
+    }
+    let Some(verb) = chars.next() else {
+        errorf!("format {fmt:?} has no verb");
+    };
+    spec.verb = verb;
+    spec.suffix = chars.collect::<String>().replace("%%", "%");
+    if spec.suffix.contains('%') {
+        errorf!("format {fmt:?} has more than one verb");
+    }
+    Ok(spec)
+}
+
+/// checks the format `fmt`, returning its verb and its precision, if it has
+/// one
+pub fn verb(fmt: &str) -> Result<(char, Option<usize>), Error> {
+    let spec = parse(fmt)?;
+    if !"dboxXceEfFgGsvq".contains(spec.verb) {
+        errorf!("unknown format verb %{}", spec.verb);
+    }
+    Ok((spec.verb, spec.prec))
+}
+
+/// formats `v` for printing with the settings in `conf`. Numbers follow the
+/// format if there is one, and the parts of a rational follow the rational
+/// format. Without a format, integers and rationals are printed in the output
+/// base, or in scientific notation if they have more than `max_digits`
+/// digits. Vectors and matrices are laid out as
+/// [`Display`](std::fmt::Display) lays them out.
+pub fn sprint(v: &Value, conf: &Config) -> String {
+    match v {
+        Value::Vector(e) if !e.iter().all(|c| matches!(c, Value::Char(_))) => {
+            let strs: Vec<_> = e.iter().map(|e| nested(e, conf)).collect();
+            strs.join(" ")
+        }
+        Value::Matrix(m) => m.format_with(|e| nested(e, conf)),
+        // a value the format cannot handle prints as if there were none
+        v => scalar(v, conf).unwrap_or_else(|_| v.to_string()),
+    }
+}
+
+/// formats an element of a vector or matrix, in parentheses if it is itself
+/// a vector or matrix
+fn nested(v: &Value, conf: &Config) -> String {
+    if v.is_vector() || v.is_matrix() {
+        format!("({})", sprint(v, conf))
+    } else {
+        sprint(v, conf)
+    }
+}
+
+fn scalar(v: &Value, conf: &Config) -> Result<String, Error> {
+    let (fmt, prec) = (conf.format(), conf.float_prec());
+    match v {
+        Value::Char(_) | Value::Vector(_) => Ok(v.to_string()),
+        Value::Rational(r) if !conf.rat_format().is_empty() => {
+            let part = |i: &BigInt| {
+                sprintf(conf.rat_format(), &Value::from(i.clone()), prec)
+            };
+            Ok(format!("{}/{}", part(r.numer())?, part(r.denom())?))
+        }
+        _ if !fmt.is_empty() => sprintf(fmt, v, prec),
+        Value::Int(_) | Value::BigInt(_) => {
+            let i = v.to_big_int().unwrap();
+            Ok(digits(&i, conf)
+                .unwrap_or_else(|| BigFloat::from_big_int(i, prec).to_string()))
+        }
+        Value::Rational(r) => {
+            match (digits(r.numer(), conf), digits(r.denom(), conf)) {
+                (Some(n), Some(d)) => Ok(format!("{n}/{d}")),
+                _ => Ok(BigFloat::from_big_rat(r, prec).to_string()),
+            }
+        }
+        v => Ok(v.to_string()),
+    }
+}
+
+/// writes `i` in the output base, or returns `None` if that takes more than
+/// `max_digits` digits, in which case it is printed as a decimal float
+fn digits(i: &BigInt, conf: &Config) -> Option<String> {
+    let base = match conf.output_base() {
+        0 => 10,
+        base => base as u32,
+    };
+    let max = conf.max_digits();
+    // the number of bits puts a floor under the number of digits
+    let floor = i.bits() as f64 / f64::from(base).log2();
+    if max != 0 && floor as usize > max {
+        return Option::None;
+    }
+    let s = i.to_str_radix(base);
+    if max != 0 && s.trim_start_matches('-').len() > max {
+        return Option::None;
+    }
+    Some(s)
+}
+
+/// formats `v` with the printf-style format `fmt`. The elements of a vector or
+/// matrix are formatted one at a time and separated by spaces, except that
+/// `%s`, `%v` and `%q` format a string as a whole. Floats are formatted at
+/// precision `prec`.
+pub fn sprintf(fmt: &str, v: &Value, prec: usize) -> Result<String, Error> {
+    let spec = parse(fmt)?;
+    match v {
+        // the string verbs format a string as a whole
+        Value::Vector(e)
+            if "sqv".contains(spec.verb)
+                && e.iter().all(|c| matches!(c, Value::Char(_))) =>
+        {
+            spec.format(v, prec)
+        }
+        Value::Vector(_) | Value::Matrix(_) => {
+            let elems = v.clone().into_elems();
+            let strs = elems
+                .iter()
+                .map(|e| spec.format(e, prec))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(strs.join(" "))
+        }
+        v => spec.format(v, prec),
+    }
+}
+
+impl Spec {
+    /// formats the scalar `v`, with the literal text around it
+    fn format(&self, v: &Value, prec: usize) -> Result<String, Error> {
+        let body = match self.verb {
+            'd' | 'b' | 'o' | 'x' | 'X' => self.integer(v, prec)?,
+            'c' => match v {
+                Value::Char(c) => c.to_string(),
+                Value::Int(i) => {
+                    match u32::try_from(*i).ok().and_then(char::from_u32) {
+                        Some(c) => c.to_string(),
+                        None => errorf!("%c: invalid character {i}"),
+                    }
+                }
+                v => errorf!("%c: {v} is not a character"),
+            },
+            'e' | 'E' | 'f' | 'F' | 'g' | 'G' => self.float(v, prec)?,
+            's' | 'v' => self.truncate(v.to_string()),
+            'q' => match v {
+                Value::Char(c) => format!("{c:?}"),
+                v => format!("{:?}", v.to_string()),
+            },
+            verb => errorf!("unknown format verb %{verb}"),
+        };
+        let body = self.sign(body, v);
+        Ok(format!(
+            "{}{}{}",
+            self.prefix,
+            self.pad(body, v),
+            self.suffix
+        ))
+    }
+
+    /// formats an integer in the base of the verb, with at least as many
+    /// digits as the precision. A rational has its numerator and denominator
+    /// formatted in turn. Other values are formatted as with `%v`.
+    fn integer(&self, v: &Value, prec: usize) -> Result<String, Error> {
+        let i = match v {
+            Value::Char(c) => BigInt::from(u32::from(*c)),
+            Value::Rational(r) => {
+                let part = |i: &BigInt| self.integer(&i.clone().into(), prec);
+                return Ok(format!(
+                    "{}/{}",
+                    part(r.numer())?,
+                    part(r.denom())?
+                ));
+            }
+            v => match v.to_big_int() {
+                Some(i) => i,
+                None if self.verb == 'd' => return self.float(v, prec),
+                None => return Ok(v.to_string()),
+            },
+        };
+        let (radix, prefix) = match self.verb {
+            'b' => (2, "0b"),
+            'o' => (8, "0"),
+            'x' | 'X' => (16, "0x"),
+            _ => (10, ""),
+        };
+        let mut digits = i.abs().to_str_radix(radix);
+        if self.verb == 'X' {
+            digits = digits.to_uppercase();
+        }
+        if let Some(p) = self.prec.filter(|&p| digits.len() < p) {
+            digits.insert_str(0, &"0".repeat(p - digits.len()));
+        }
+        let prefix = if self.sharp { prefix } else { "" };
+        let sign = if i.is_negative() { "-" } else { "" };
+        Ok(format!("{sign}{prefix}{digits}"))
+    }
+
+    /// formats a number as a decimal float. A complex number has its parts
+    /// formatted separately.
+    fn float(&self, v: &Value, prec: usize) -> Result<String, Error> {
+        if let Value::Complex(c) = v {
+            let re =
+                self.float(&BigFloat::from_f64(c.re, prec)?.into(), prec)?;
+            let im =
+                self.float(&BigFloat::from_f64(c.im, prec)?.into(), prec)?;
+            let sign = if im.starts_with('-') { "" } else { "+" };
+            return Ok(format!("{re}{sign}{im}i"));
+        }
+        let (Some(r), Some(f)) = (v.to_big_rat(), v.to_big_float(prec)) else {
+            let r = match v {
+                Value::Float(f) => f.to_big_rat(),
+                v => errorf!("%{}: {v} is not a number", self.verb),
+            };
+            return self.float(&Value::Rational(r), prec);
+        };
+        let s = match self.verb {
+            'e' | 'E' => f.format_e(self.prec.unwrap_or(6)),
+            'f' | 'F' => fixed(&r, self.prec.unwrap_or(6)),
+            // without a precision, %g shows every digit the float holds
+            _ => {
+                let digits = prec as f64 * std::f64::consts::LOG10_2;
+                f.format_g(self.prec.unwrap_or(digits as usize))
+            }
+        };
+        Ok(if self.verb.is_uppercase() {
+            s.to_uppercase()
+        } else {
+            s
+        })
+    }
+
+    /// cuts a string down to the precision, if there is one
+    fn truncate(&self, s: String) -> String {
+        match self.prec {
+            Some(p) => s.chars().take(p).collect(),
+            None => s,
+        }
+    }
+
+    /// adds the sign requested by the `+` or space flag to a non-negative
+    /// number
+    fn sign(&self, body: String, v: &Value) -> String {
+        let numeric = !matches!(v, Value::Char(_)) && v.to_f64().is_some();
+        if !numeric || body.starts_with('-') || "cqsv".contains(self.verb) {
+            return body;
+        }
+        if self.plus {
+            format!("+{body}")
+        } else if self.space {
+            format!(" {body}")
+        } else {
+            body
+        }
+    }
+
+    /// pads the formatted value to the width, on the left unless the `-` flag
+    /// is set. The `0` flag pads numbers with zeros after the sign.
+    fn pad(&self, body: String, v: &Value) -> String {
+        let n = body.chars().count();
+        let Some(width) = self.width.filter(|&w| w > n) else {
+            return body;
+        };
+        let fill = width - n;
+        if self.minus {
+            return format!("{body}{}", " ".repeat(fill));
+        }
+        if self.zero && v.to_f64().is_some() {
+            // the zeros go after the sign and any 0b or 0x prefix
+            let digits = body.trim_start_matches(['+', '-', ' ']);
+            let mut split = body.len() - digits.len();
+            if self.sharp && "bxX".contains(self.verb) {
+                split += 2;
+            }
+            let (sign, digits) = body.split_at(split);
+            return format!("{sign}{}{digits}", "0".repeat(fill));
+        }
+        format!("{}{body}", " ".repeat(fill))
+    }
+}
+
+/// formats `r` in fixed point with `prec` digits after the decimal point,
+/// rounding half away from zero
+fn fixed(r: &BigRational, prec: usize) -> String {
+    let scale = num::pow(BigInt::from(10), prec);
+    let scaled = r.abs() * BigRational::from_integer(scale);
+    let (num, den) = (scaled.numer(), scaled.denom());
+    let q: BigInt = (num * 2u32 + den) / (den * 2u32);
+    let digits = format!("{q:0>width$}", width = prec + 1);
+    let (int, frac) = digits.split_at(digits.len() - prec);
+    let sign = if r.is_negative() && !q.is_zero() {
+        "-"
+    } else {
+        ""
+    };
+    if prec == 0 {
+        format!("{sign}{int}")
+    } else {
+        format!("{sign}{int}.{frac}")
+    }
+}
+
+#[test]
+fn verbs() {
+    let f = |fmt, v| sprintf(fmt, &v, 256).unwrap();
+    assert_eq!(f("%x", Value::Int(255)), "ff");
+    assert_eq!(f("#08b", Value::Int(5)), "0b000101");
+    assert_eq!(f("%5d|", Value::Int(-42)), "  -42|");
+    assert_eq!(
+        f(
+            "%.3f",
+            Value::rational(Value::Int(2), Value::Int(3)).unwrap()
+        ),
+        "0.667"
+    );
+    assert_eq!(f("%.2e", Value::Int(12345)), "1.23e+04");
+    assert_eq!(f("%c", Value::Int(0x61)), "a");
+    let v = Value::Vector(vec![Value::Int(1), Value::Int(2)]);
+    assert_eq!(f("<%d>", v), "<1> <2>");
+    let r = Value::rational(Value::Int(-255), Value::Int(16)).unwrap();
+    assert_eq!(f("%#x", r.clone()), "-0xff/0x10");
+    assert_eq!(f("%d", r), "-255/16");
+    assert!(sprintf("%.1000000000f", &Value::Int(3), 256).is_err());
+    assert!(sprintf("%99999999999999999999d", &Value::Int(3), 256).is_err());
+}
+
+#[test]
+fn printing() {
+    use crate::config::Options;
+    let third = Value::rational(Value::Int(1), Value::Int(3)).unwrap();
+    let big = Value::from(num::pow(BigInt::from(10), 30));
+    let p = |fmt: &str, max_digits, v: &Value| {
+        let format = String::from(fmt);
+        let opts = Options {
+            format,
+            max_digits,
+            ..Options::default()
+        };
+        sprint(v, &Config::new(opts))
+    };
+    assert_eq!(p("", 0, &third), "1/3");
+    assert_eq!(p("%.3f", 0, &third), "0.333");
+    assert_eq!(p("%04d", 0, &third), "0001/0003");
+    assert_eq!(p("", 0, &big), format!("1{}", "0".repeat(30)));
+    assert_eq!(p("", 10, &big), "1e+30");
+    let hex = Config::new(Options {
+        output_base: 16,
+        ..Options::default()
+    });
+    let v = Value::rational(Value::Int(-255), Value::Int(16)).unwrap();
+    assert_eq!(sprint(&v, &hex), "-ff/10");
+    let m = Value::Matrix(super::matrix::Matrix::new(
+        vec![2, 2],
+        vec![third.clone(), Value::Int(2), Value::Int(30), Value::Int(4)],
+    ));
+    assert_eq!(p("%.2f", 0, &m), " 0.33 2.00\n30.00 4.00");
+}
+
+#[test]
+fn output_bases() {
+    use crate::config::Options;
+    let p = |output_base, v: &Value| {
+        let opts = Options {
+            output_base,
+            ..Options::default()
+        };
+        sprint(v, &Config::new(opts))
+    };
+    let v = Value::Vector(vec![
+        Value::Int(5),
+        Value::Int(255),
+        Value::rational(Value::Int(-5), Value::Int(3)).unwrap(),
+    ]);
+    assert_eq!(p(2, &v), "101 11111111 -101/11");
+    assert_eq!(p(16, &v), "5 ff -5/3");
+    assert_eq!(p(10, &v), "5 255 -5/3");
+}
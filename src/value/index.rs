@@ -0,0 +1,160 @@
+//! indexing of vectors and matrices, as in `v[3]`, `m[1; 2]` and `m[; 2]`.
+//! Each index selects positions along one axis of the indexed value, counted
+//! from the configured origin. A scalar index removes its axis from the result,
+//! a vector or matrix index replaces the axis with its own shape, and an
+//! elided index, or one missing from the end of the list, keeps the whole axis.
+
+use super::{
+    matrix::{shape_string, shaped},
+    Value,
+};
+
+use crate::error::{errorf, Error};
+
+/// Axis is the selection made by one index: the positions chosen along the
+/// axis and the shape they take in the result
+struct Axis {
+    pos: Vec<usize>,
+    shape: Vec<usize>,
+}
+
+/// returns the elements of `v` selected by `index`. A `None` index selects
+/// the whole of its axis.
+pub fn index(
+    v: Value,
+    index: &[Option<Value>],
+    origin: usize,
+) -> Result<Value, Error> {
+    let (shape, offsets) = select(&v, index, origin)?;
+    let data = v.into_elems();
+    // an element may be selected more than once, so each is copied
+    let elems = offsets.into_iter().map(|i| data[i].clone()).collect();
+    Ok(shaped(shape, elems))
+}
+
+/// stores `value` in the elements of `v` selected by `index`. A single
+/// element takes the value as is; otherwise a scalar is stored in every
+/// selected element and an array must have the shape of the selection.
+pub fn assign(
+    v: Value,
+    index: &[Option<Value>],
+    origin: usize,
+    value: Value,
+) -> Result<Value, Error> {
+    let (shape, offsets) = select(&v, index, origin)?;
+    let old_shape = v.shape();
+    let mut data = v.into_elems();
+    if shape.is_empty() {
+        data[offsets[0]] = value;
+    } else if value.shape().is_empty() {
+        for i in offsets {
+            data[i] = value.clone();
+        }
+    } else if value.shape() == shape {
+        for (i, e) in offsets.into_iter().zip(value.into_elems()) {
+            data[i] = e;
+        }
+    } else {
+        errorf!(
+            "cannot assign value of shape {} to index of shape {}",
+            shape_string(&value.shape()),
+            shape_string(&shape)
+        );
+    }
+    Ok(shaped(old_shape, data))
+}
+
+/// returns the shape of the selection made by `index` from `v` and the
+/// offsets of the selected elements in the row-major data of `v`
+fn select(
+    v: &Value,
+    index: &[Option<Value>],
+    origin: usize,
+) -> Result<(Vec<usize>, Vec<usize>), Error> {
+    let dims = v.shape();
+    if dims.is_empty() {
+        errorf!("cannot index {v}");
+    }
+    if index.len() > dims.len() {
+        errorf!(
+            "too many indices ({}) for value of rank {}",
+            index.len(),
+            dims.len()
+        );
+    }
+    let axes = dims
+        .iter()
+        .enumerate()
+        .map(|(d, &n)| match index.get(d) {
+            Some(Some(i)) => axis(i, n, origin),
+            _ => Ok(Axis {
+                pos: (0..n).collect(),
+                shape: vec![n],
+            }),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // walk the selected positions in row-major order, like an odometer
+    let mut offsets = Vec::new();
+    if axes.iter().all(|a| !a.pos.is_empty()) {
+        let mut counter = vec![0; axes.len()];
+        'outer: loop {
+            let offset = axes
+                .iter()
+                .zip(&counter)
+                .zip(&dims)
+                .fold(0, |off, ((a, &c), &n)| off * n + a.pos[c]);
+            offsets.push(offset);
+            for d in (0..axes.len()).rev() {
+                counter[d] += 1;
+                if counter[d] < axes[d].pos.len() {
+                    continue 'outer;
+                }
+                counter[d] = 0;
+            }
+            break;
+        }
+    }
+    let shape = axes.into_iter().flat_map(|a| a.shape).collect();
+    Ok((shape, offsets))
+}
+
+/// returns the positions selected by the index `i` along an axis of length
+/// `n`
+fn axis(i: &Value, n: usize, origin: usize) -> Result<Axis, Error> {
+    let shape = i.shape();
+    let pos = match i {
+        Value::Vector(v) => v.iter().map(|e| position(e, n, origin)).collect(),
+        Value::Matrix(m) => {
+            m.data().iter().map(|e| position(e, n, origin)).collect()
+        }
+        e => position(e, n, origin).map(|p| vec![p]),
+    }?;
+    Ok(Axis { pos, shape })
+}
+
+/// converts a single index into a position counted from zero
+fn position(i: &Value, n: usize, origin: usize) -> Result<usize, Error> {
+    let p = match i {
+        Value::Int(p) => *p,
+        Value::BigInt(_) => errorf!("index {i} out of range"),
+        _ => errorf!("invalid index {i}"),
+    };
+    match p.checked_sub(origin as i64) {
+        Some(p) if (0..n as i64).contains(&p) => Ok(p as usize),
+        _ => errorf!("index {i} out of range for axis of length {n}"),
+    }
+}
+
+#[test]
+fn select_and_assign() {
+    let m = shaped(vec![2, 3], (1..=6).map(Value::Int).collect());
+    let col = index(m.clone(), &[None, Some(Value::Int(2))], 1);
+    assert_eq!(col, Ok(Value::Vector(vec![Value::Int(2), Value::Int(5)])));
+    // origin 0 selects from zero
+    let elem = index(m.clone(), &[Some(Value::Int(1)), Some(Value::Int(0))], 0);
+    assert_eq!(elem, Ok(Value::Int(4)));
+    assert!(index(m.clone(), &[Some(Value::Int(3))], 1).is_err());
+    let row = assign(m, &[Some(Value::Int(1))], 1, Value::Int(0)).unwrap();
+    assert_eq!(row.into_elems()[..4], [0, 0, 0, 4].map(Value::Int));
+}
@@ -0,0 +1,208 @@
+use std::fmt::Display;
+
+use super::Value;
+
+use crate::{
+    config::Config,
+    error::{errorf, Error},
+};
+
+/// Matrix is a multidimensional array of values. The elements are stored flat
+/// in row-major order, so the last dimension of `shape` varies fastest.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Matrix {
+    shape: Vec<usize>,
+    data: Vec<Value>,
+}
+
+impl Matrix {
+    /// returns a new matrix with the given shape. Panics if the number of
+    /// elements in `data` does not match the shape.
+    pub fn new(shape: Vec<usize>, data: Vec<Value>) -> Self {
+        assert_eq!(
+            shape.iter().product::<usize>(),
+            data.len(),
+            "matrix data does not match shape {shape:?}"
+        );
+        Self { shape, data }
+    }
+
+    pub fn shape(&self) -> &[usize] {
+        self.shape.as_ref()
+    }
+
+    pub fn data(&self) -> &[Value] {
+        self.data.as_ref()
+    }
+
+    pub fn into_data(self) -> Vec<Value> {
+        self.data
+    }
+
+    /// returns the number of dimensions
+    pub fn rank(&self) -> usize {
+        self.shape.len()
+    }
+
+    /// returns the matrix with the order of its axes reversed, so that the
+    /// element at (i, j, k) moves to (k, j, i)
+    pub fn transpose(&self) -> Self {
+        let shape: Vec<_> = self.shape.iter().rev().copied().collect();
+        // the strides of the original axes, in the order of the new ones
+        let mut strides = vec![1; shape.len()];
+        for d in 1..shape.len() {
+            strides[d] = strides[d - 1] * shape[d - 1];
+        }
+        let mut data = Vec::with_capacity(self.data.len());
+        let mut counter = vec![0; shape.len()];
+        for _ in 0..self.data.len() {
+            let off: usize =
+                counter.iter().zip(&strides).map(|(c, s)| c * s).sum();
+            data.push(self.data[off].clone());
+            for d in (0..shape.len()).rev() {
+                counter[d] += 1;
+                if counter[d] < shape[d] {
+                    break;
+                }
+                counter[d] = 0;
+            }
+        }
+        Self { shape, data }
+    }
+
+    /// applies `f` to every element, preserving the shape. Stops at the first
+    /// error.
+    pub fn map(
+        self,
+        f: impl FnMut(Value) -> Result<Value, Error>,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            shape: self.shape,
+            data: self.data.into_iter().map(f).collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// writes the matrix one row per line, with each column right-aligned and
+    /// each element formatted by `elem`. The planes of a 3-dimensional matrix
+    /// are separated by a blank line, and each additional dimension adds
+    /// another.
+    pub(crate) fn format_with(
+        &self,
+        elem: impl Fn(&Value) -> String,
+    ) -> String {
+        let cols = *self.shape.last().unwrap_or(&0);
+        if cols == 0 || self.data.is_empty() {
+            return String::new();
+        }
+        let all_chars = self.data.iter().all(|e| matches!(e, Value::Char(_)));
+        let strs: Vec<_> = self.data.iter().map(elem).collect();
+        let widths: Vec<_> = (0..cols)
+            .map(|c| {
+                strs.iter()
+                    .skip(c)
+                    .step_by(cols)
+                    .map(|s| s.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let rank = self.rank();
+        let mut w = String::new();
+        for (r, row) in strs.chunks(cols).enumerate() {
+            if r > 0 {
+                w.push('\n');
+                let mut block = 1;
+                for dim in self.shape[..rank - 1].iter().skip(1).rev() {
+                    block *= dim;
+                    if r % block == 0 {
+                        w.push('\n');
+                    }
+                }
+            }
+            for (c, s) in row.iter().enumerate() {
+                if all_chars {
+                    w.push_str(s);
+                    continue;
+                }
+                if c > 0 {
+                    w.push(' ');
+                }
+                w.push_str(&format!("{s:>width$}", width = widths[c]));
+            }
+        }
+        w
+    }
+}
+
+/// reshapes `data` into `shape`, repeating the elements cyclically to fill the
+/// result, which is shaped as by [`shaped`]. A shape with more elements than
+/// `conf` allows is an error.
+pub fn reshape(
+    conf: &Config,
+    shape: Vec<usize>,
+    data: Vec<Value>,
+) -> Result<Value, Error> {
+    let n = shape.iter().try_fold(1usize, |n, &d| n.checked_mul(d));
+    let n = n.unwrap_or(usize::MAX);
+    conf.check_len(n)?;
+    if n > 0 && data.is_empty() {
+        errorf!("rho: no data to reshape");
+    }
+    let data: Vec<_> = data.into_iter().cycle().take(n).collect();
+    Ok(shaped(shape, data))
+}
+
+/// returns `data` as a value of the given shape: the only element for an empty
+/// shape, a vector for a single dimension and a matrix for more. Panics if the
+/// number of elements does not match the shape.
+pub fn shaped(shape: Vec<usize>, mut data: Vec<Value>) -> Value {
+    match shape.len() {
+        0 => {
+            assert_eq!(data.len(), 1, "scalar must have one element");
+            data.pop().unwrap()
+        }
+        1 => Value::Vector(data),
+        _ => Value::Matrix(Matrix::new(shape, data)),
+    }
+}
+
+/// formats a shape for error messages, as ivy would print it: the dimensions
+/// separated by spaces
+pub(crate) fn shape_string(shape: &[usize]) -> String {
+    let s: Vec<_> = shape.iter().map(usize::to_string).collect();
+    s.join(" ")
+}
+
+impl Display for Matrix {
+    /// writes the matrix as [`Matrix::format_with`] does, with vectors and
+    /// matrices within it in parentheses
+    fn fmt(&self, w: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = self.format_with(|e| {
+            if e.is_vector() || e.is_matrix() {
+                format!("({e})")
+            } else {
+                e.to_string()
+            }
+        });
+        write!(w, "{s}")
+    }
+}
+
+#[test]
+fn reshaping() {
+    let conf = Config::default();
+    let five = || vec![Value::Int(5)];
+    assert_eq!(reshape(&conf, vec![], five()), Ok(Value::Int(5)));
+    let v = reshape(&conf, vec![3], five());
+    assert_eq!(v, Ok(Value::Vector(vec![Value::Int(5); 3])));
+    let huge = 1 << 32;
+    assert!(reshape(&conf, vec![huge, huge], five()).is_err());
+    assert!(reshape(&conf, vec![huge, huge, huge], five()).is_err());
+}
+
+#[test]
+fn display() {
+    let data = (1..=12).map(Value::Int).collect();
+    let m = Matrix::new(vec![2, 2, 3], data);
+    assert_eq!(m.to_string(), " 1  2  3\n 4  5  6\n\n 7  8  9\n10 11 12");
+}